@@ -1,34 +1,153 @@
 use std::env;
-#[cfg(feature = "static")]
-use std::process::Command;
-
-#[cfg(feature = "static")]
-use cmake::Config;
+#[cfg(not(feature = "static"))]
+use std::path::Path;
+use std::path::PathBuf;
 
 fn main() {
-    build();
+    // docs.rs only builds the documentation, which needs neither libpcapng nor its sources
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+    if env::var_os("DOCS_RS").is_some() {
+        return;
+    }
+    let include_dir = build();
+    if let Some(include_dir) = &include_dir {
+        println!("cargo:include={}", include_dir.display());
+    }
+    #[cfg(feature = "bindgen")]
+    generate_bindings(include_dir);
+}
+
+/// Links against an installed libpcapng found, in order, through the `LIBPCAPNG_LIB_DIR`
+/// environment variable, pkg-config or the usual library directories
+///
+/// `LIBPCAPNG_STATIC` links the static library instead of the shared one and `LIBPCAPNG_INCLUDE_DIR`
+/// names the directory holding `libpcapng/libpcapng.h` when it is not installed with the library.
+/// How the library was found is exposed to the crate as `LIBPCAPNG_DISCOVERY`.
+#[cfg(not(feature = "static"))]
+fn build() -> Option<PathBuf> {
+    for var in ["LIBPCAPNG_LIB_DIR", "LIBPCAPNG_INCLUDE_DIR", "LIBPCAPNG_STATIC"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+    let link_static = env::var("LIBPCAPNG_STATIC").is_ok_and(|value| value != "0");
+    let kind = if link_static { "static" } else { "dylib" };
+
+    let (discovery, include_dirs) = if let Some(lib_dir) = env::var_os("LIBPCAPNG_LIB_DIR") {
+        println!("cargo:rustc-link-search=native={}", PathBuf::from(lib_dir).display());
+        println!("cargo:rustc-link-lib={}=pcapng", kind);
+        ("lib-dir", Vec::new())
+    } else if let Some(library) = probe_pkg_config(link_static) {
+        ("pkg-config", library.include_paths)
+    } else if let Some(lib_dir) = find_library_dir(link_static) {
+        println!("cargo:rustc-link-search=native={}", lib_dir.display());
+        println!("cargo:rustc-link-lib={}=pcapng", kind);
+        ("search", Vec::new())
+    } else {
+        panic!(
+            "could not find libpcapng: install it where pkg-config can find it, set LIBPCAPNG_LIB_DIR \
+             to the directory containing libpcapng, or enable the `static` feature to build it from source"
+        );
+    };
+    println!("cargo:rustc-env=LIBPCAPNG_DISCOVERY={}", discovery);
+
+    env::var_os("LIBPCAPNG_INCLUDE_DIR").map(PathBuf::from).or_else(|| include_dirs.into_iter().next())
 }
 
 #[cfg(not(feature = "static"))]
-fn build() {
-    println!("cargo:rustc-link-search=/usr/local/lib");
-    println!("cargo:rustc-link-lib=pcapng");
+fn probe_pkg_config(link_static: bool) -> Option<pkg_config::Library> {
+    ["libpcapng", "pcapng"].into_iter().find_map(|name| {
+        pkg_config::Config::new().statik(link_static).probe(name).ok()
+    })
 }
 
+/// Looks for the library in the directories it is usually installed to, including the
+/// multiarch directory of the target such as `/usr/lib/x86_64-linux-gnu`
+#[cfg(not(feature = "static"))]
+fn find_library_dir(link_static: bool) -> Option<PathBuf> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let parts: Vec<&str> = target.split('-').collect();
+    let multiarch = match parts[..] {
+        [arch, _vendor, os, env] => format!("{}-{}-{}", arch, os, env),
+        _ => target.clone(),
+    };
+    let file_names: &[&str] = if link_static {
+        &["libpcapng.a"]
+    } else {
+        &["libpcapng.so", "libpcapng.dylib", "libpcapng.a"]
+    };
+    let candidates = [
+        PathBuf::from("/usr/local/lib"),
+        Path::new("/usr/lib").join(multiarch),
+        PathBuf::from("/usr/lib64"),
+        PathBuf::from("/usr/lib"),
+        PathBuf::from("/opt/homebrew/lib"),
+    ];
+    candidates.into_iter().find(|dir| file_names.iter().any(|name| dir.join(name).exists()))
+}
+
+/// The sources of the core library in libpcapng's `lib` directory, leaving out the `wandio` file
+/// readers and `pybind11` bindings so neither is needed to build
+#[cfg(feature = "static")]
+const LIBPCAPNG_SOURCES: &[&str] = &["libpcapng.c"];
+
+#[cfg(feature = "static")]
+fn build() -> Option<PathBuf> {
+    let src_dir = libpcapng_src_dir();
+    let lib_dir = src_dir.join("lib");
+    let mut build = cc::Build::new();
+    build.include(lib_dir.join("include"))
+        .include(lib_dir.join("include").join("libpcapng"))
+        .warnings(false);
+    for name in LIBPCAPNG_SOURCES {
+        let source = lib_dir.join(name);
+        if !source.is_file() {
+            panic!("libpcapng source {} not found, LIBPCAPNG_SRC_DIR must name a checkout of libpcapng", source.display());
+        }
+        println!("cargo:rerun-if-changed={}", source.display());
+        build.file(source);
+    }
+    build.compile("pcapng_static");
+    println!("cargo:rustc-env=LIBPCAPNG_DISCOVERY=static");
+    Some(lib_dir.join("include"))
+}
+
+/// The libpcapng sources named by the `LIBPCAPNG_SRC_DIR` environment variable, nothing is
+/// downloaded so the build runs offline and always compiles the sources it was given
 #[cfg(feature = "static")]
-fn build() {
-    let out_dir = env::var("OUT_DIR").unwrap();
-    let mut proc = Command::new("git").current_dir(out_dir.to_owned())
-        .arg("clone")
-        .arg("https://github.com/stricaud/libpcapng.git")
-        .spawn()
-        .expect("error executing git command");
-    let s = proc.wait().unwrap();
-    if !s.success() {
-        panic!("failed to init submodule")
+fn libpcapng_src_dir() -> PathBuf {
+    println!("cargo:rerun-if-env-changed=LIBPCAPNG_SRC_DIR");
+    match env::var_os("LIBPCAPNG_SRC_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => panic!(
+            "LIBPCAPNG_SRC_DIR is not set: the `static` feature compiles libpcapng from source, set \
+             LIBPCAPNG_SRC_DIR to the root of a checkout of https://github.com/stricaud/libpcapng, the \
+             directory containing lib/libpcapng.c, or disable `static` to link an installed libpcapng"
+        ),
     }
+}
 
-    let dst = Config::new(format!("{}/libpcapng",out_dir)).build_target("pcapng_static").build();
-    println!("cargo:rustc-link-search=native={}/build/lib", dst.display());
-    println!("cargo:rustc-link-lib=static=pcapng_static");
-}
\ No newline at end of file
+/// Generates the bindings from the `libpcapng.h` being built or linked against into
+/// `OUT_DIR/bindings.rs`, in place of the committed `src/bindings.rs`
+#[cfg(feature = "bindgen")]
+fn generate_bindings(include_dir: Option<PathBuf>) {
+    let mut builder = bindgen::Builder::default()
+        .header_contents("wrapper.h", "#include <stdio.h>\n#include <libpcapng/libpcapng.h>\n")
+        .allowlist_function(".*pcapng_.*")
+        .allowlist_var("PCAPNG_.*")
+        // the glibc internals behind FILE and size_t are left to libc so the bindings do not
+        // depend on the C library they were generated against
+        .blocklist_type("FILE|_IO_.*|__off(64)?_t|__uint(8|16|32|64)_t|size_t")
+        .raw_line("pub type FILE = libc::FILE;")
+        .raw_line("pub type size_t = libc::size_t;")
+        .size_t_is_usize(false)
+        .layout_tests(false)
+        .generate_comments(false)
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+    if let Some(include_dir) = include_dir {
+        builder = builder.clang_arg(format!("-I{}", include_dir.display()));
+    }
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+    builder.generate()
+        .expect("unable to generate bindings from libpcapng.h, set LIBPCAPNG_INCLUDE_DIR to its include directory")
+        .write_to_file(out_path)
+        .expect("unable to write bindings");
+}
@@ -1,6 +1,11 @@
 
 /* automatically generated by rust-bindgen 0.60.1 */
 
+/// Binding for respective libpcapng type
+pub type FILE = libc::FILE;
+/// Binding for respective libpcapng type
+pub type size_t = libc::size_t;
+
 /// Binding for respective libpcapng const
 pub const PCAPNG_PEN: u32 = 31337;
 /// Binding for respective libpcapng const
@@ -76,49 +81,6 @@ pub const PCAPNG_ZIGBEE_APS_KEY: u32 = 1514229843;
 /// Binding for respective libpcapng const
 pub const PCAPNG_BLOCK_TYPE_AND_SIZE_LENGTH: u32 = 8;
 
-
-/// Binding for respective libpcapng type
-pub type size_t = libc::size_t;
-/// Binding for respective libpcapng type
-pub type __uint16_t = ::std::os::raw::c_ushort;
-/// Binding for respective libpcapng type
-pub type __uint32_t = ::std::os::raw::c_uint;
-/// Binding for respective libpcapng type
-pub type __uint64_t = ::std::os::raw::c_ulong;
-/// Binding for respective libpcapng type
-pub type __off_t = ::std::os::raw::c_long;
-/// Binding for respective libpcapng type
-pub type __off64_t = ::std::os::raw::c_long;
-/// Binding for respective libpcapng type
-pub type FILE = libc::FILE;
-
-/// Binding for respective libpcapng struct
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct _IO_marker {
-    /// Binding for respective libpcapng struct field
-    _unused: [u8; 0],
-}
-
-/// Binding for respective libpcapng struct
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct _IO_codecvt {
-    /// Binding for respective libpcapng struct field
-    _unused: [u8; 0],
-}
-
-/// Binding for respective libpcapng struct
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct _IO_wide_data {
-    /// Binding for respective libpcapng struct field
-    _unused: [u8; 0],
-}
-
-/// Binding for respective libpcapng type
-pub type _IO_lock_t = ::std::os::raw::c_void;
-
 /// Binding for respective libpcapng struct
 #[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
@@ -10,25 +10,56 @@
 //!
 //! ## Build Requirements
 //!
-//! * the GNU toolchain
-//! * GNU `make`
-//! * `wandio`
-//! * `pybind11`
-//! * `cmake`
+//! * a C compiler
+//!
+//! With the `static` feature the core of libpcapng is compiled with the `cc` crate, without
+//! its `wandio` readers or `pybind11` bindings. The sources are taken from a checkout of libpcapng
+//! named by the `LIBPCAPNG_SRC_DIR` environment variable, nothing is downloaded during the build
+//! and it fails when the variable is not set.
+//!
+//! By default an installed libpcapng is linked instead, found through pkg-config or
+//! the usual library directories such as `/usr/lib/x86_64-linux-gnu`. The search can be overridden
+//! with these environment variables:
+//!
+//! * `LIBPCAPNG_LIB_DIR` the directory containing the library
+//! * `LIBPCAPNG_INCLUDE_DIR` the directory containing `libpcapng/libpcapng.h`
+//! * `LIBPCAPNG_STATIC` links the static library instead of the shared one when set
+//!
+//! ## Features
+//!
+//! `static` compiles libpcapng from the sources in `LIBPCAPNG_SRC_DIR` and links it statically
+//! `bindgen` generates the bindings at build time from the `libpcapng.h` being compiled or linked
+//! against, which needs libclang, instead of using the snapshot committed in `src/bindings.rs`
 
-#![forbid(missing_docs)]
+#![cfg_attr(not(feature = "bindgen"), forbid(missing_docs))]
+#![cfg_attr(feature = "bindgen", deny(missing_docs))]
 #![allow(clippy::type_complexity)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
-// Create new bindings with bindgen
+// Create new bindings with bindgen, with the same options as the `bindgen` feature in build.rs,
+// then restore the doc comments
 // bindgen \
 // --allowlist-function=".*pcapng_.*" \
-// --allowlist-var="PCAPNG_.*" wrapper.h  > src/bindings.rs
+// --allowlist-var="PCAPNG_.*" \
+// --blocklist-type="FILE|_IO_.*|__off(64)?_t|__uint(8|16|32|64)_t|size_t" \
+// --raw-line="pub type FILE = libc::FILE;" \
+// --raw-line="pub type size_t = libc::size_t;" \
+// --no-size_t-is-usize --no-layout-tests --no-doc-comments wrapper.h \
+// -- -I"$LIBPCAPNG_SRC_DIR/lib/include" > src/bindings.rs
+#[cfg(not(feature = "bindgen"))]
 include!("bindings.rs");
 
+#[cfg(feature = "bindgen")]
+#[allow(missing_docs)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+#[cfg(feature = "bindgen")]
+pub use generated::*;
+
 
 
 
@@ -37,19 +68,50 @@ mod tests {
     use std::fs;
     use std::path::Path;
 
-    use libc::{c_char, fopen};
+    use libc::{fclose, fopen};
 
     use crate::libpcapng_write_header_to_file;
 
     #[test]
     fn read_write_header() {
         unsafe {
-            let x = "pcap.pcapng\0".as_ptr() as *const c_char;
-            let outfh = fopen("pcap.pcapng\0".as_ptr(), "wb\0".as_ptr());
+            let outfh = fopen(c"pcap.pcapng".as_ptr(), c"wb".as_ptr());
             libpcapng_write_header_to_file(outfh);
+            fclose(outfh);
             let path = Path::new("pcap.pcapng");
             assert!(path.exists());
             fs::remove_file(path).expect("Unable to cleanuup afte test");
         }
     }
+
+    /// Set `LIBPCAPNG_EXPECTED_DISCOVERY` to `lib-dir`, `pkg-config`, `search` or `static` to check
+    /// the build script found the library the expected way
+    #[test]
+    fn library_discovery_test() {
+        if let Some(expected) = option_env!("LIBPCAPNG_EXPECTED_DISCOVERY") {
+            assert_eq!(env!("LIBPCAPNG_DISCOVERY"), expected);
+        }
+    }
+
+    /// Strips comments and whitespace so bindings generated by different bindgen versions compare equal
+    #[cfg(feature = "bindgen")]
+    fn normalize_bindings(bindings: &str) -> String {
+        bindings.lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with("//") && !line.starts_with("/*"))
+            .flat_map(|line| line.chars().filter(|c| !c.is_whitespace()))
+            .collect::<String>()
+            .replace("unsafeextern\"C\"", "extern\"C\"")
+    }
+
+    #[cfg(feature = "bindgen")]
+    #[test]
+    fn bindings_snapshot_test() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+        let committed = include_str!("bindings.rs");
+        assert!(
+            normalize_bindings(generated) == normalize_bindings(committed),
+            "src/bindings.rs has diverged from libpcapng.h, regenerate it with bindgen and restore the doc comments"
+        );
+    }
 }
@@ -1,3 +1,3 @@
 #include <stdio.h>
 
-#include "../libpcapng/lib/include/libpcapng/libpcapng.h"
+#include <libpcapng/libpcapng.h>
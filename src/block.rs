@@ -0,0 +1,503 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, SystemTime};
+use crate::{PCAPNG_BYTE_ORDER_MAGIC, PCAPNG_CUSTOM_DATA_BLOCK, PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY, PCAPNG_DECRYPTION_SECRETS_BLOCK, PCAPNG_ENHANCED_PACKET_BLOCK, PCAPNG_INTERFACE_DESCRIPTION_BLOCK, PCAPNG_INTERFACE_STATISTICS_BLOCK, PCAPNG_NAME_RESOLUTION_BLOCK, PCAPNG_PACKET_BLOCK, PCAPNG_SECTION_HEADER_BLOCK, PCAPNG_SIMPLE_PACKET_BLOCK};
+use crate::PcapNgError::{MalformedBlock, ValueTooLong};
+use crate::timestamp::ticks_to_system_time;
+use crate::{BlockOption, RawBlock, Timestamp, TsResolution};
+
+/// A decoded pcapng block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Block {
+    /// Section Header Block, starts a new section
+    SectionHeader(SectionHeader),
+    /// Interface Description Block, declares an interface packets can be captured on
+    InterfaceDescription(InterfaceDescription),
+    /// Enhanced Packet Block, a captured packet, also used for the obsolete Packet Block
+    EnhancedPacket(EnhancedPacket),
+    /// Simple Packet Block, a captured packet without interface or timestamp
+    SimplePacket(SimplePacket),
+    /// Name Resolution Block, maps addresses to names
+    NameResolution(NameResolution),
+    /// Interface Statistics Block, capture statistics for an interface
+    InterfaceStatistics(InterfaceStatistics),
+    /// Decryption Secrets Block, key material for decrypting captured traffic
+    DecryptionSecrets(DecryptionSecrets),
+    /// Custom Block, application data tagged with a Private Enterprise Number
+    Custom(CustomBlock),
+    /// Any other block type, left undecoded
+    Unknown(RawBlock),
+}
+
+/// The fields of a Section Header Block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SectionHeader {
+    /// Major version of the format, 1 for the current version
+    pub major_version: u16,
+    /// Minor version of the format
+    pub minor_version: u16,
+    /// Length in bytes of the section following this block, -1 when not specified
+    pub section_length: i64,
+    /// The options attached to the block
+    pub options: Vec<BlockOption>,
+}
+
+/// The fields of an Interface Description Block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InterfaceDescription {
+    /// The LINKTYPE_* link layer type of the interface
+    pub link_type: u16,
+    /// Maximum number of bytes captured from each packet, 0 for no limit
+    pub snaplen: u32,
+    /// The options attached to the block
+    pub options: Vec<BlockOption>,
+}
+
+impl InterfaceDescription {
+    /// The resolution of the interface's timestamps from its `if_tsresol` option, microseconds when absent
+    pub fn ts_resolution(&self) -> TsResolution {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::IfTsResol(resolution) => Some(*resolution),
+            _ => None,
+        }).unwrap_or_default()
+    }
+
+    /// Seconds added to the interface's timestamps from its `if_tsoffset` option, 0 when absent
+    pub fn ts_offset(&self) -> i64 {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::IfTsOffset(offset) => Some(*offset),
+            _ => None,
+        }).unwrap_or(0)
+    }
+
+    /// Converts a timestamp to ticks of the interface's resolution relative to its offset
+    pub(crate) fn ticks(&self, timestamp: Timestamp) -> u64 {
+        let resolution = self.ts_resolution();
+        let ticks = timestamp.to_ticks(resolution);
+        let offset = self.ts_offset();
+        if offset == 0 {
+            return ticks;
+        }
+        let offset_ticks = Timestamp::SinceEpoch(Duration::from_secs(offset.unsigned_abs())).to_ticks(resolution);
+        if offset > 0 { ticks.saturating_sub(offset_ticks) } else { ticks.saturating_add(offset_ticks) }
+    }
+}
+
+/// The fields of an Enhanced Packet Block
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EnhancedPacket {
+    /// Id of the interface the packet was captured on, its position among the section's interfaces
+    pub interface_id: u32,
+    /// Upper 32 bits of the timestamp
+    pub timestamp_high: u32,
+    /// Lower 32 bits of the timestamp
+    pub timestamp_low: u32,
+    /// Number of bytes of the packet which were captured
+    pub captured_len: u32,
+    /// Length of the packet on the wire
+    pub original_len: u32,
+    /// The captured packet bytes
+    pub data: Vec<u8>,
+    /// The options attached to the block
+    pub options: Vec<BlockOption>,
+    /// Resolution of the timestamp, taken from the packet's interface when read with
+    /// [`PcapNg::blocks`](crate::PcapNg::blocks) and not written when encoding
+    pub ts_resolution: TsResolution,
+    /// Seconds to add to the timestamp, taken from the packet's interface when read with
+    /// [`PcapNg::blocks`](crate::PcapNg::blocks) and not written when encoding
+    pub ts_offset: i64,
+}
+
+impl EnhancedPacket {
+    /// The time the packet was captured
+    pub fn timestamp(&self) -> SystemTime {
+        let ticks = (self.timestamp_high as u64) << 32 | self.timestamp_low as u64;
+        ticks_to_system_time(ticks, self.ts_resolution, self.ts_offset)
+    }
+}
+
+/// The fields of a Simple Packet Block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SimplePacket {
+    /// Length of the packet on the wire
+    pub original_len: u32,
+    /// The captured packet bytes
+    pub data: Vec<u8>,
+}
+
+/// The fields of a Name Resolution Block
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct NameResolution {
+    /// The name resolution records in the order they appear in the block
+    pub records: Vec<NameRecord>,
+    /// The options attached to the block
+    pub options: Vec<BlockOption>,
+}
+
+/// A single record of a Name Resolution Block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NameRecord {
+    /// Names for an IPv4 address
+    Ipv4 {
+        /// The address
+        address: Ipv4Addr,
+        /// The names the address resolves to
+        names: Vec<String>,
+    },
+    /// Names for an IPv6 address
+    Ipv6 {
+        /// The address
+        address: Ipv6Addr,
+        /// The names the address resolves to
+        names: Vec<String>,
+    },
+    /// A record type this crate does not know about
+    Unknown {
+        /// The record type
+        record_type: u16,
+        /// The record value without padding
+        value: Vec<u8>,
+    },
+}
+
+/// The fields of an Interface Statistics Block
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct InterfaceStatistics {
+    /// Id of the interface the statistics refer to
+    pub interface_id: u32,
+    /// Upper 32 bits of the time the statistics were taken
+    pub timestamp_high: u32,
+    /// Lower 32 bits of the time the statistics were taken
+    pub timestamp_low: u32,
+    /// The options attached to the block
+    pub options: Vec<BlockOption>,
+    /// Resolution of the timestamps, taken from the block's interface when read with
+    /// [`PcapNg::blocks`](crate::PcapNg::blocks) and not written when encoding
+    pub ts_resolution: TsResolution,
+    /// Seconds to add to the timestamps, taken from the block's interface when read with
+    /// [`PcapNg::blocks`](crate::PcapNg::blocks) and not written when encoding
+    pub ts_offset: i64,
+}
+
+/// The fields of a Decryption Secrets Block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DecryptionSecrets {
+    /// The format of the secrets, one of the `PCAPNG_*_KEY*` constants for known formats
+    pub secrets_type: u32,
+    /// The secrets without padding
+    pub data: Vec<u8>,
+    /// The options attached to the block
+    pub options: Vec<BlockOption>,
+}
+
+/// The fields of a Custom Block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CustomBlock {
+    /// Whether the block may be copied to new files by tools which do not understand it
+    pub copyable: bool,
+    /// The Private Enterprise Number identifying the owner of the data
+    pub pen: u32,
+    /// The custom data, including any padding added when it was written
+    pub data: Vec<u8>,
+}
+
+impl Block {
+    /// Decodes the body of a block read from the pcap
+    ///
+    /// Obsolete packet blocks are decoded as [`Block::EnhancedPacket`] and encoded back as enhanced packet blocks.
+    pub fn decode(raw: RawBlock) -> crate::Result<Block> {
+        let mut body = BodyReader::new(raw.body());
+        let block = match raw.block_type {
+            PCAPNG_SECTION_HEADER_BLOCK => {
+                body.u32()?;
+                Block::SectionHeader(SectionHeader {
+                    major_version: body.u16()?,
+                    minor_version: body.u16()?,
+                    section_length: body.u64()? as i64,
+                    options: BlockOption::parse_all(raw.block_type, body.rest())?,
+                })
+            }
+            PCAPNG_INTERFACE_DESCRIPTION_BLOCK => {
+                let link_type = body.u16()?;
+                body.u16()?;
+                Block::InterfaceDescription(InterfaceDescription {
+                    link_type,
+                    snaplen: body.u32()?,
+                    options: BlockOption::parse_all(raw.block_type, body.rest())?,
+                })
+            }
+            PCAPNG_ENHANCED_PACKET_BLOCK => {
+                let interface_id = body.u32()?;
+                let timestamp_high = body.u32()?;
+                let timestamp_low = body.u32()?;
+                let captured_len = body.u32()?;
+                let original_len = body.u32()?;
+                Block::EnhancedPacket(EnhancedPacket {
+                    interface_id,
+                    timestamp_high,
+                    timestamp_low,
+                    captured_len,
+                    original_len,
+                    data: body.padded_bytes(captured_len as usize)?.to_vec(),
+                    options: BlockOption::parse_all(raw.block_type, body.rest())?,
+                    ts_resolution: TsResolution::default(),
+                    ts_offset: 0,
+                })
+            }
+            PCAPNG_PACKET_BLOCK => {
+                // the obsolete packet block is decoded as an enhanced packet, its drop counter
+                // becoming an epb_dropcount option
+                let interface_id = body.u16()? as u32;
+                let drops_count = body.u16()?;
+                let timestamp_high = body.u32()?;
+                let timestamp_low = body.u32()?;
+                let captured_len = body.u32()?;
+                let original_len = body.u32()?;
+                let data = body.padded_bytes(captured_len as usize)?.to_vec();
+                let mut options = BlockOption::parse_all(raw.block_type, body.rest())?;
+                if drops_count != u16::MAX {
+                    options.push(BlockOption::EpbDropCount(drops_count as u64));
+                }
+                Block::EnhancedPacket(EnhancedPacket {
+                    interface_id,
+                    timestamp_high,
+                    timestamp_low,
+                    captured_len,
+                    original_len,
+                    data,
+                    options,
+                    ts_resolution: TsResolution::default(),
+                    ts_offset: 0,
+                })
+            }
+            PCAPNG_SIMPLE_PACKET_BLOCK => {
+                let original_len = body.u32()?;
+                let data = body.rest();
+                let captured_len = data.len().min(original_len as usize);
+                Block::SimplePacket(SimplePacket {
+                    original_len,
+                    data: data[..captured_len].to_vec(),
+                })
+            }
+            PCAPNG_NAME_RESOLUTION_BLOCK => {
+                let mut records = Vec::new();
+                loop {
+                    let record_type = body.u16()?;
+                    let value_length = body.u16()?;
+                    let value = body.padded_bytes(value_length as usize)?;
+                    match record_type {
+                        0 => break,
+                        1 if value.len() >= 4 => records.push(NameRecord::Ipv4 {
+                            address: Ipv4Addr::from(<[u8; 4]>::try_from(&value[..4]).unwrap()),
+                            names: decode_names(&value[4..]),
+                        }),
+                        2 if value.len() >= 16 => records.push(NameRecord::Ipv6 {
+                            address: Ipv6Addr::from(<[u8; 16]>::try_from(&value[..16]).unwrap()),
+                            names: decode_names(&value[16..]),
+                        }),
+                        1 | 2 => return Err(MalformedBlock(format!("name resolution record of {} bytes is too short", value.len()))),
+                        _ => records.push(NameRecord::Unknown {
+                            record_type,
+                            value: value.to_vec(),
+                        }),
+                    }
+                }
+                Block::NameResolution(NameResolution {
+                    records,
+                    options: BlockOption::parse_all(raw.block_type, body.rest())?,
+                })
+            }
+            PCAPNG_INTERFACE_STATISTICS_BLOCK => Block::InterfaceStatistics(InterfaceStatistics {
+                interface_id: body.u32()?,
+                timestamp_high: body.u32()?,
+                timestamp_low: body.u32()?,
+                options: BlockOption::parse_all(raw.block_type, body.rest())?,
+                ts_resolution: TsResolution::default(),
+                ts_offset: 0,
+            }),
+            PCAPNG_DECRYPTION_SECRETS_BLOCK => {
+                let secrets_type = body.u32()?;
+                let secrets_length = body.u32()?;
+                Block::DecryptionSecrets(DecryptionSecrets {
+                    secrets_type,
+                    data: body.padded_bytes(secrets_length as usize)?.to_vec(),
+                    options: BlockOption::parse_all(raw.block_type, body.rest())?,
+                })
+            }
+            PCAPNG_CUSTOM_DATA_BLOCK | PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY => Block::Custom(CustomBlock {
+                copyable: raw.block_type == PCAPNG_CUSTOM_DATA_BLOCK,
+                pen: body.u32()?,
+                data: body.rest().to_vec(),
+            }),
+            _ => Block::Unknown(raw),
+        };
+        Ok(block)
+    }
+
+    /// The pcapng block type of the block
+    pub fn block_type(&self) -> u32 {
+        match self {
+            Block::SectionHeader(_) => PCAPNG_SECTION_HEADER_BLOCK,
+            Block::InterfaceDescription(_) => PCAPNG_INTERFACE_DESCRIPTION_BLOCK,
+            Block::EnhancedPacket(_) => PCAPNG_ENHANCED_PACKET_BLOCK,
+            Block::SimplePacket(_) => PCAPNG_SIMPLE_PACKET_BLOCK,
+            Block::NameResolution(_) => PCAPNG_NAME_RESOLUTION_BLOCK,
+            Block::InterfaceStatistics(_) => PCAPNG_INTERFACE_STATISTICS_BLOCK,
+            Block::DecryptionSecrets(_) => PCAPNG_DECRYPTION_SECRETS_BLOCK,
+            Block::Custom(custom) if custom.copyable => PCAPNG_CUSTOM_DATA_BLOCK,
+            Block::Custom(_) => PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY,
+            Block::Unknown(raw) => raw.block_type,
+        }
+    }
+
+    /// Serializes the block including its header and trailing length
+    ///
+    /// The captured length of packets is taken from the length of their data. Options, name
+    /// records or blocks too long for their length fields are returned as
+    /// [`PcapNgError::ValueTooLong`](crate::PcapNgError::ValueTooLong).
+    pub fn encode(&self) -> crate::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        match self {
+            Block::SectionHeader(shb) => {
+                body.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_ne_bytes());
+                body.extend_from_slice(&shb.major_version.to_ne_bytes());
+                body.extend_from_slice(&shb.minor_version.to_ne_bytes());
+                body.extend_from_slice(&shb.section_length.to_ne_bytes());
+                BlockOption::encode_all(&shb.options, &mut body)?;
+            }
+            Block::InterfaceDescription(idb) => {
+                body.extend_from_slice(&idb.link_type.to_ne_bytes());
+                body.extend_from_slice(&0u16.to_ne_bytes());
+                body.extend_from_slice(&idb.snaplen.to_ne_bytes());
+                BlockOption::encode_all(&idb.options, &mut body)?;
+            }
+            Block::EnhancedPacket(epb) => {
+                body.extend_from_slice(&epb.interface_id.to_ne_bytes());
+                body.extend_from_slice(&epb.timestamp_high.to_ne_bytes());
+                body.extend_from_slice(&epb.timestamp_low.to_ne_bytes());
+                body.extend_from_slice(&(epb.data.len() as u32).to_ne_bytes());
+                body.extend_from_slice(&epb.original_len.to_ne_bytes());
+                put_padded(&mut body, &epb.data);
+                BlockOption::encode_all(&epb.options, &mut body)?;
+            }
+            Block::SimplePacket(spb) => {
+                body.extend_from_slice(&spb.original_len.to_ne_bytes());
+                put_padded(&mut body, &spb.data);
+            }
+            Block::NameResolution(nrb) => {
+                for record in &nrb.records {
+                    let (record_type, value) = match record {
+                        NameRecord::Ipv4 { address, names } => (1u16, [&address.octets()[..], &encode_names(names)].concat()),
+                        NameRecord::Ipv6 { address, names } => (2u16, [&address.octets()[..], &encode_names(names)].concat()),
+                        NameRecord::Unknown { record_type, value } => (*record_type, value.clone()),
+                    };
+                    let length = u16::try_from(value.len()).map_err(|_| ValueTooLong("name resolution record".to_string(), value.len()))?;
+                    body.extend_from_slice(&record_type.to_ne_bytes());
+                    body.extend_from_slice(&length.to_ne_bytes());
+                    put_padded(&mut body, &value);
+                }
+                body.extend_from_slice(&[0u8; 4]);
+                BlockOption::encode_all(&nrb.options, &mut body)?;
+            }
+            Block::InterfaceStatistics(isb) => {
+                body.extend_from_slice(&isb.interface_id.to_ne_bytes());
+                body.extend_from_slice(&isb.timestamp_high.to_ne_bytes());
+                body.extend_from_slice(&isb.timestamp_low.to_ne_bytes());
+                BlockOption::encode_all(&isb.options, &mut body)?;
+            }
+            Block::DecryptionSecrets(dsb) => {
+                body.extend_from_slice(&dsb.secrets_type.to_ne_bytes());
+                body.extend_from_slice(&(dsb.data.len() as u32).to_ne_bytes());
+                put_padded(&mut body, &dsb.data);
+                BlockOption::encode_all(&dsb.options, &mut body)?;
+            }
+            Block::Custom(custom) => {
+                body.extend_from_slice(&custom.pen.to_ne_bytes());
+                put_padded(&mut body, &custom.data);
+            }
+            Block::Unknown(raw) => body.extend_from_slice(raw.body()),
+        }
+
+        let block_total_length = u32::try_from(body.len() + 12).map_err(|_| ValueTooLong("block".to_string(), body.len() + 12))?;
+        let mut bytes = Vec::with_capacity(block_total_length as usize);
+        bytes.extend_from_slice(&self.block_type().to_ne_bytes());
+        bytes.extend_from_slice(&block_total_length.to_ne_bytes());
+        bytes.extend_from_slice(&body);
+        bytes.extend_from_slice(&block_total_length.to_ne_bytes());
+        Ok(bytes)
+    }
+}
+
+/// Appends `bytes` followed by the padding up to the next 32 bit boundary
+pub(crate) fn put_padded(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + padding(bytes.len()), 0);
+}
+
+/// Joins names into the zero terminated form used by name resolution records
+fn encode_names(names: &[String]) -> Vec<u8> {
+    names.iter().flat_map(|name| name.bytes().chain(std::iter::once(0))).collect()
+}
+
+/// Splits the zero terminated names of a name resolution record
+fn decode_names(bytes: &[u8]) -> Vec<String> {
+    bytes.split(|b| *b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect()
+}
+
+/// Reads native endian fields from a block body, failing with a malformed block error when
+/// the body is too short
+pub(crate) struct BodyReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> BodyReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        BodyReader { bytes, offset: 0 }
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> crate::Result<&'a [u8]> {
+        if self.bytes.len() - self.offset < len {
+            return Err(MalformedBlock(format!("expected {} more bytes but only {} remain", len, self.bytes.len() - self.offset)));
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    /// Reads `len` bytes and skips the padding up to the next 32 bit boundary
+    pub(crate) fn padded_bytes(&mut self, len: usize) -> crate::Result<&'a [u8]> {
+        let bytes = self.bytes(len)?;
+        self.offset = (self.offset + padding(len)).min(self.bytes.len());
+        Ok(bytes)
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.bytes[self.offset..];
+        self.offset = self.bytes.len();
+        bytes
+    }
+
+    pub(crate) fn u16(&mut self) -> crate::Result<u16> {
+        Ok(u16::from_ne_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> crate::Result<u32> {
+        Ok(u32::from_ne_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> crate::Result<u64> {
+        Ok(u64::from_ne_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
+
+/// Number of zero bytes needed after `len` bytes to reach a 32 bit boundary
+pub(crate) fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
@@ -0,0 +1,36 @@
+/// Private Enterprise Number libpcapng writes its custom blocks with
+pub const PCAPNG_PEN: u32 = 31337;
+/// Major version of the format written in section headers
+pub const PCAPNG_VERSION_MAJOR: u32 = 1;
+/// Minor version of the format written in section headers
+pub const PCAPNG_VERSION_MINOR: u32 = 0;
+/// Magic number of a section header, read back swapped when written in the other byte order
+pub const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+/// Interface Description Block type
+pub const PCAPNG_INTERFACE_DESCRIPTION_BLOCK: u32 = 1;
+/// Obsolete Packet Block type
+pub const PCAPNG_PACKET_BLOCK: u32 = 2;
+/// Simple Packet Block type
+pub const PCAPNG_SIMPLE_PACKET_BLOCK: u32 = 3;
+/// Name Resolution Block type
+pub const PCAPNG_NAME_RESOLUTION_BLOCK: u32 = 4;
+/// Interface Statistics Block type
+pub const PCAPNG_INTERFACE_STATISTICS_BLOCK: u32 = 5;
+/// Enhanced Packet Block type
+pub const PCAPNG_ENHANCED_PACKET_BLOCK: u32 = 6;
+/// Decryption Secrets Block type
+pub const PCAPNG_DECRYPTION_SECRETS_BLOCK: u32 = 10;
+/// Custom Block type which may be copied to new files
+pub const PCAPNG_CUSTOM_DATA_BLOCK: u32 = 0x0000_0BAD;
+/// Custom Block type which should not be copied to new files
+pub const PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY: u32 = 0x4000_0BAD;
+/// Section Header Block type
+pub const PCAPNG_SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+/// Decryption secrets type of a TLS key log
+pub const PCAPNG_TLS_KEY_LOG: u32 = 0x544C_534B;
+/// Decryption secrets type of a WireGuard key log
+pub const PCAPNG_WIREGUARD_KEY_LOG: u32 = 0x5747_4B4C;
+/// Decryption secrets type of ZigBee network keys
+pub const PCAPNG_ZIGBEE_NWK_KEY: u32 = 0x5A4E_574B;
+/// Decryption secrets type of ZigBee application support keys
+pub const PCAPNG_ZIGBEE_APS_KEY: u32 = 0x5A41_5053;
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use crate::CustomBlock;
+use crate::PcapNgError::CustomCodecError;
+
+/// Converts application values to and from the data of custom blocks owned by one Private
+/// Enterprise Number
+///
+/// Codecs for the different kinds of data an application stores are registered in a
+/// [`CustomBlockRegistry`] which decodes them into one user type `T`, usually an enum.
+pub trait CustomBlockCodec<T> {
+    /// The Private Enterprise Number of the blocks this codec handles
+    fn pen(&self) -> u32;
+
+    /// Decodes the data of a block, which includes any padding added when it was written
+    fn decode(&self, data: &[u8]) -> Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Encodes a value into the data of a block
+    fn encode(&self, value: &T) -> Vec<u8>;
+}
+
+/// A custom block read through a [`CustomBlockRegistry`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CustomValue<T> {
+    /// A block decoded by the codec registered for its Private Enterprise Number
+    Decoded {
+        /// The Private Enterprise Number of the block
+        pen: u32,
+        /// The decoded value
+        value: T,
+    },
+    /// A block with no registered codec, left as it was read
+    Raw(CustomBlock),
+}
+
+/// The codecs used to decode custom blocks, keyed by Private Enterprise Number
+pub struct CustomBlockRegistry<T> {
+    codecs: HashMap<u32, Box<dyn CustomBlockCodec<T>>>,
+}
+
+impl<T> Default for CustomBlockRegistry<T> {
+    fn default() -> Self {
+        CustomBlockRegistry { codecs: HashMap::new() }
+    }
+}
+
+impl<T> CustomBlockRegistry<T> {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a codec, replacing any codec already registered for its Private Enterprise Number
+    pub fn register<C: CustomBlockCodec<T> + 'static>(mut self, codec: C) -> Self {
+        self.codecs.insert(codec.pen(), Box::new(codec));
+        self
+    }
+
+    /// Decodes a custom block with the codec registered for its Private Enterprise Number,
+    /// returning it unchanged when there is none
+    pub fn decode(&self, custom: CustomBlock) -> crate::Result<CustomValue<T>> {
+        match self.codecs.get(&custom.pen) {
+            Some(codec) => match codec.decode(&custom.data) {
+                Ok(value) => Ok(CustomValue::Decoded { pen: custom.pen, value }),
+                Err(e) => Err(CustomCodecError(custom.pen, e)),
+            },
+            None => Ok(CustomValue::Raw(custom)),
+        }
+    }
+}
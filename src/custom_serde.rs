@@ -0,0 +1,27 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::PcapNg;
+use crate::PcapNgError::CustomCodecError;
+
+impl PcapNg {
+    /// Writes `value` encoded as CBOR in a copyable custom block owned by the Private Enterprise Number `pen`
+    pub fn write_custom_serde<T: Serialize>(&mut self, pen: u32, value: &T) -> crate::Result<()> {
+        let mut data = Vec::new();
+        ciborium::into_writer(value, &mut data).map_err(|e| CustomCodecError(pen, e.into()))?;
+        self.write_custom_with_pen(pen, true, data)
+    }
+
+    /// Reads the rest of the pcap and decodes the CBOR value of every custom block owned by the
+    /// Private Enterprise Number `pen`, other custom blocks are skipped
+    pub fn read_custom_serde<T: DeserializeOwned>(&mut self, pen: u32) -> crate::Result<Vec<T>> {
+        let mut values = Vec::new();
+        for custom in self.read_custom()? {
+            if custom.pen == pen {
+                // the padding after the value is never read
+                let value = ciborium::from_reader(custom.data.as_slice()).map_err(|e| CustomCodecError(pen, e.into()))?;
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+}
@@ -9,6 +9,12 @@ pub enum PcapNgError {
     /// Indicates there was an error closing the pcap file
     #[error("error closing file")]
     FileCloseError,
+    /// Indicates there was an error reading from the pcap file
+    #[error("error reading file")]
+    FileReadError,
+    /// Indicates there was an error writing to the pcap file
+    #[error("error writing file")]
+    FileWriteError,
     /// Indicates that the file was never opened
     #[error("file has not been opened")]
     FileNotOpen,
@@ -18,6 +24,36 @@ pub enum PcapNgError {
     /// This error is raised if using a write operation on a pcap opened in read mode
     #[error("this operation is only supported in write or append mode")]
     OperationOnlySupportedInWriteMode,
+    /// Raised when setting the section header of a file which already has one
+    #[error("the section header has already been written")]
+    SectionHeaderAlreadyWritten,
+    /// Indicates a block referred to an interface id with no matching interface description block
+    #[error("interface {0} has not been declared")]
+    UnknownInterface(u32),
+    /// Indicates a packet's original length was shorter than the bytes captured from it
+    #[error("original length {0} is shorter than the {1} captured bytes")]
+    OriginalLengthTooShort(u32, usize),
+    /// Indicates a block in the pcap could not be parsed
+    #[error("malformed block: {0}")]
+    MalformedBlock(String),
+    /// Indicates a value was too long for the length field it is written with
+    #[error("{0} of {1} bytes is too long to encode")]
+    ValueTooLong(String, usize),
+    /// Indicates a section was written with a byte order different from this host's
+    #[error("sections in a byte order other than the host's are not supported")]
+    UnsupportedByteOrder,
+    /// Indicates the output of a copy is the file being read, which writing would truncate
+    #[error("the output file is the input file")]
+    OutputIsInput,
+    /// Indicates a line of a key log file is not in the NSS key log format
+    #[error("key log line {0} is malformed: {1}")]
+    MalformedKeyLog(usize, String),
+    /// The error returned by the codec registered for a custom block's Private Enterprise Number
+    #[error("custom block codec for PEN {0} failed: {1}")]
+    CustomCodecError(u32, Box<dyn std::error::Error + Send + Sync>),
+    /// The error returned by a read callback which stopped reading
+    #[error("read callback failed: {0}")]
+    CallbackError(Box<dyn std::error::Error + Send + Sync>),
     /// A catch all for other unknown errors
     #[error("unknown error {0}")]
     UnknownError(String),
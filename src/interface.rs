@@ -0,0 +1,128 @@
+use crate::{BlockOption, InterfaceDescription, TsResolution};
+
+/// `LINKTYPE_NULL`, BSD loopback encapsulation
+pub const LINKTYPE_NULL: u16 = 0;
+/// `LINKTYPE_ETHERNET`, IEEE 802.3 Ethernet
+pub const LINKTYPE_ETHERNET: u16 = 1;
+/// `LINKTYPE_RAW`, raw IPv4 or IPv6 packets without a link layer header
+pub const LINKTYPE_RAW: u16 = 101;
+/// `LINKTYPE_IEEE802_11`, IEEE 802.11 wireless frames
+pub const LINKTYPE_IEEE802_11: u16 = 105;
+/// `LINKTYPE_LINUX_SLL`, Linux cooked capture
+pub const LINKTYPE_LINUX_SLL: u16 = 113;
+/// `LINKTYPE_IEEE802_11_RADIOTAP`, IEEE 802.11 frames preceded by a radiotap header
+pub const LINKTYPE_IEEE802_11_RADIOTAP: u16 = 127;
+/// `LINKTYPE_LINUX_SLL2`, Linux cooked capture v2
+pub const LINKTYPE_LINUX_SLL2: u16 = 276;
+
+/// Builder for the Interface Description Block written by [`PcapNg::add_interface`](crate::PcapNg::add_interface)
+///
+/// ```rust
+/// use libpcapng_rs::{Interface, TsResolution, LINKTYPE_ETHERNET};
+///
+/// let interface = Interface::new(LINKTYPE_ETHERNET)
+///     .snaplen(262144)
+///     .name("eth0")
+///     .ts_resolution(TsResolution::NANOSECONDS);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Interface {
+    link_type: u16,
+    snaplen: u32,
+    name: Option<String>,
+    description: Option<String>,
+    ts_resolution: Option<TsResolution>,
+    filter: Option<String>,
+    os: Option<String>,
+    fcs_len: Option<u8>,
+}
+
+impl Interface {
+    /// Creates an interface with the given `LINKTYPE_*` link type and no snaplen limit
+    pub fn new(link_type: u16) -> Self {
+        Interface {
+            link_type,
+            snaplen: 0,
+            name: None,
+            description: None,
+            ts_resolution: None,
+            filter: None,
+            os: None,
+            fcs_len: None,
+        }
+    }
+
+    /// Sets the maximum number of bytes captured from each packet, 0 means no limit
+    pub fn snaplen(mut self, snaplen: u32) -> Self {
+        self.snaplen = snaplen;
+        self
+    }
+
+    /// Sets `if_name`, the name of the interface
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets `if_description`, a description of the interface
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets `if_tsresol`, the resolution of the interface's timestamps
+    pub fn ts_resolution(mut self, ts_resolution: TsResolution) -> Self {
+        self.ts_resolution = Some(ts_resolution);
+        self
+    }
+
+    /// Sets `if_filter` to a libpcap filter string
+    pub fn filter<S: Into<String>>(mut self, filter: S) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Sets `if_os`, the operating system of the machine the interface is on
+    pub fn os<S: Into<String>>(mut self, os: S) -> Self {
+        self.os = Some(os.into());
+        self
+    }
+
+    /// Sets `if_fcslen`, the length of the frame check sequence included in each packet
+    pub fn fcs_len(mut self, fcs_len: u8) -> Self {
+        self.fcs_len = Some(fcs_len);
+        self
+    }
+}
+
+impl From<Interface> for InterfaceDescription {
+    fn from(interface: Interface) -> Self {
+        let mut options = Vec::new();
+        if let Some(name) = interface.name {
+            options.push(BlockOption::IfName(name));
+        }
+        if let Some(description) = interface.description {
+            options.push(BlockOption::IfDescription(description));
+        }
+        if let Some(ts_resolution) = interface.ts_resolution {
+            options.push(BlockOption::IfTsResol(ts_resolution));
+        }
+        if let Some(filter) = interface.filter {
+            options.push(BlockOption::IfFilter {
+                filter_type: 0,
+                filter: filter.into_bytes(),
+            });
+        }
+        if let Some(os) = interface.os {
+            options.push(BlockOption::IfOs(os));
+        }
+        if let Some(fcs_len) = interface.fcs_len {
+            options.push(BlockOption::IfFcsLen(fcs_len));
+        }
+        InterfaceDescription {
+            link_type: interface.link_type,
+            snaplen: interface.snaplen,
+            options,
+        }
+    }
+}
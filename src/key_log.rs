@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::{PCAPNG_ENHANCED_PACKET_BLOCK, PCAPNG_PACKET_BLOCK, PCAPNG_SECTION_HEADER_BLOCK, PCAPNG_SIMPLE_PACKET_BLOCK};
+use crate::{Block, DecryptionSecrets, PcapNg, PcapNgOpenMode, SecretsKind};
+use crate::PcapNgError::{FileReadError, FileWriteError, MalformedBlock, MalformedKeyLog, OutputIsInput};
+
+/// Copies the capture at `capture` to `output` with the TLS secrets of the NSS key log file at
+/// `key_log` written in a decryption secrets block before the first packet, in the same way as
+/// `editcap --inject-secrets`
+///
+/// Every line of the key log is validated first and the first malformed one is returned as
+/// [`PcapNgError::MalformedKeyLog`](crate::PcapNgError::MalformedKeyLog), with nothing written.
+/// The capture must start with a section header block and `output` must be a different file,
+/// otherwise [`PcapNgError::MalformedBlock`](crate::PcapNgError::MalformedBlock) or
+/// [`PcapNgError::OutputIsInput`](crate::PcapNgError::OutputIsInput) is returned.
+pub fn inject_key_log<C, K, O>(capture: C, key_log: K, output: O) -> crate::Result<()>
+where
+    C: Into<PathBuf>,
+    K: Into<PathBuf>,
+    O: Into<PathBuf>,
+{
+    let (capture, output) = (capture.into(), output.into());
+    let secrets = fs::read(key_log.into()).map_err(|_| FileReadError)?;
+    validate_key_log(&secrets)?;
+    let mut secrets_block = Some(Block::DecryptionSecrets(DecryptionSecrets::new(SecretsKind::TlsKeyLog, secrets)));
+    // opening the output for writing truncates it, which would destroy the capture being read
+    if let (Ok(capture), Ok(output)) = (fs::canonicalize(&capture), fs::canonicalize(&output)) {
+        if capture == output {
+            return Err(OutputIsInput);
+        }
+    }
+
+    let mut reader = PcapNg::new(capture, PcapNgOpenMode::Read);
+    reader.open()?;
+    let mut blocks = reader.raw_blocks()?;
+    let section_header = match blocks.next() {
+        Some(raw) => raw?,
+        None => return Err(MalformedBlock("the capture is empty".to_string())),
+    };
+    if section_header.block_type != PCAPNG_SECTION_HEADER_BLOCK {
+        return Err(MalformedBlock("the capture does not start with a section header block".to_string()));
+    }
+    let mut writer = PcapNg::new(output, PcapNgOpenMode::Write);
+    writer.open_file()?;
+    writer.write_bytes(&Block::Unknown(section_header).encode()?)?;
+    for raw in blocks {
+        let raw = raw?;
+        if matches!(raw.block_type, PCAPNG_ENHANCED_PACKET_BLOCK | PCAPNG_SIMPLE_PACKET_BLOCK | PCAPNG_PACKET_BLOCK) {
+            if let Some(block) = secrets_block.take() {
+                writer.write_bytes(&block.encode()?)?;
+            }
+        }
+        // an undecoded block is written back exactly as it was read
+        writer.write_bytes(&Block::Unknown(raw).encode()?)?;
+    }
+    if let Some(block) = secrets_block {
+        writer.write_bytes(&block.encode()?)?;
+    }
+    writer.close();
+    reader.close();
+    Ok(())
+}
+
+/// Writes the secrets of every decryption secrets block in the capture at `capture` to one file
+/// per secrets type in `directory`, returning the files written in the order their types were found
+///
+/// TLS secrets are written to `tls.keys` in the `SSLKEYLOGFILE` format and WireGuard secrets to
+/// `wireguard.keys` in the format of WireGuard's key log, `zigbee_nwk.keys` and `zigbee_aps.keys`
+/// hold ZigBee keys and any other type is written to `secrets_<type>.bin`. The secrets of blocks
+/// of the same type are concatenated, separated by a newline for the text formats.
+pub fn extract_key_logs<C, D>(capture: C, directory: D) -> crate::Result<Vec<(SecretsKind, PathBuf)>>
+where
+    C: Into<PathBuf>,
+    D: AsRef<Path>,
+{
+    let mut reader = PcapNg::new(capture, PcapNgOpenMode::Read);
+    reader.open()?;
+    let secrets = reader.read_decryption_secrets();
+    reader.close();
+
+    let mut files: Vec<(SecretsKind, Vec<u8>)> = Vec::new();
+    for (kind, data) in secrets? {
+        let contents = match files.iter_mut().find(|(known, _)| *known == kind) {
+            Some((_, contents)) => contents,
+            None => {
+                files.push((kind, Vec::new()));
+                &mut files.last_mut().unwrap().1
+            }
+        };
+        let is_text = matches!(kind, SecretsKind::TlsKeyLog | SecretsKind::WireGuardKeyLog);
+        if is_text && !contents.is_empty() && !contents.ends_with(b"\n") {
+            contents.push(b'\n');
+        }
+        contents.extend_from_slice(&data);
+    }
+
+    let mut written = Vec::new();
+    for (kind, contents) in files {
+        let path = directory.as_ref().join(key_log_file_name(kind));
+        fs::write(&path, contents).map_err(|_| FileWriteError)?;
+        written.push((kind, path));
+    }
+    Ok(written)
+}
+
+/// The name of the file [`extract_key_logs`] writes secrets of the given type to
+fn key_log_file_name(kind: SecretsKind) -> String {
+    match kind {
+        SecretsKind::TlsKeyLog => "tls.keys".to_string(),
+        SecretsKind::WireGuardKeyLog => "wireguard.keys".to_string(),
+        SecretsKind::ZigBeeNwkKey => "zigbee_nwk.keys".to_string(),
+        SecretsKind::ZigBeeApsKey => "zigbee_aps.keys".to_string(),
+        SecretsKind::Other(secrets_type) => format!("secrets_{:08x}.bin", secrets_type),
+    }
+}
+
+/// Checks every line of a key log is empty, a `#` comment or a `<label> <client random> <secret>`
+/// entry with hex values, of the expected lengths for the labels known here
+fn validate_key_log(key_log: &[u8]) -> crate::Result<()> {
+    for (index, line) in key_log.split(|b| *b == b'\n').enumerate() {
+        let line_number = index + 1;
+        let line = std::str::from_utf8(line)
+            .map_err(|_| MalformedKeyLog(line_number, "not valid UTF-8".to_string()))?
+            .trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(' ').collect();
+        let [label, client_random, secret] = fields[..] else {
+            return Err(MalformedKeyLog(line_number, format!("expected 3 fields, found {}", fields.len())));
+        };
+        let (random_lengths, secret_lengths): (&[usize], &[usize]) = match label {
+            "CLIENT_RANDOM" => (&[64], &[96]),
+            // the first 8 bytes of the encrypted pre-master secret
+            "RSA" => (&[16], &[96]),
+            "CLIENT_EARLY_TRAFFIC_SECRET" | "CLIENT_HANDSHAKE_TRAFFIC_SECRET" | "SERVER_HANDSHAKE_TRAFFIC_SECRET"
+            | "CLIENT_TRAFFIC_SECRET_0" | "SERVER_TRAFFIC_SECRET_0" | "EARLY_EXPORTER_SECRET" | "EXPORTER_SECRET" => (&[64], &[64, 96]),
+            // labels added after this list, such as ECH_SECRET, are only checked for their syntax
+            _ if is_label(label) => (&[64], &[]),
+            _ => return Err(MalformedKeyLog(line_number, format!("invalid label {}", label))),
+        };
+        if !is_hex(client_random, random_lengths) {
+            return Err(MalformedKeyLog(line_number, format!("invalid client random {}", client_random)));
+        }
+        let secret_valid = if secret_lengths.is_empty() {
+            !secret.is_empty() && secret.len() % 2 == 0 && secret.bytes().all(|b| b.is_ascii_hexdigit())
+        } else {
+            is_hex(secret, secret_lengths)
+        };
+        if !secret_valid {
+            return Err(MalformedKeyLog(line_number, "invalid secret".to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn is_label(label: &str) -> bool {
+    !label.is_empty() && label.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+}
+
+fn is_hex(value: &str, lengths: &[usize]) -> bool {
+    lengths.contains(&value.len()) && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
@@ -11,9 +11,28 @@
 //!
 //! - Create new PCAP file
 //! - Append to existing PCAP file
-//! - Write network packet frames with and without a timestamp
+//! - Write network packet frames with and without a high resolution timestamp
 //! - Write custom frames
+//! - Read and write compact simple packet blocks
+//! - Read the obsolete packet blocks of legacy captures
+//! - Write custom blocks with your own Private Enterprise Number
+//! - Decode custom blocks into your own types with a codec per Private Enterprise Number
+//! - Store serde values as CBOR in custom blocks with the `serde` feature
 //! - Read frames from pcap
+//! - Iterate over the blocks of a pcap one at a time
+//! - Decode blocks into typed structs
+//! - Read and write block options such as comments
+//! - Describe interfaces with their link type, snaplen and options
+//! - Write packets captured on several interfaces to one file
+//! - Annotate packets with comments, flags and other packet options
+//! - Truncate packets while recording their original length
+//! - Record the hardware, OS and application that created a file
+//! - Read and write name resolution blocks
+//! - Record interface statistics such as kernel drop counters
+//! - Embed TLS, WireGuard and ZigBee decryption secrets
+//! - Inject an `SSLKEYLOGFILE` into an existing capture
+//! - Extract embedded secrets back to key log files
+//! - Build without the C library using the `pure-rust` feature
 //!
 //! ## Installation
 //!
@@ -25,24 +44,35 @@
 //! ```
 //!
 //! This crate will compile libpcapng from sources and link it statically to
-//! your executable. To compile libpcapng you'll need:
+//! your executable. To compile libpcapng you'll need a C compiler, the core
+//! library is built with the `cc` crate without `wandio` or `pybind11`.
 //!
-//! * the GNU toolchain
-//! * GNU `make`
-//! * `wandio`
-//! * `pybind11`
-//! * `cmake`
 //!
+//! The sources are taken from a checkout of libpcapng named by the `LIBPCAPNG_SRC_DIR`
+//! environment variable, nothing is downloaded during the build and it fails when the variable
+//! is not set.
 //!
-//! By default a submodule with the libpcapng sources will be used to compile
-//! and statically link the library. The disabling default features allows
-//! to instead dynamically link libpcapng-rs to the system's libpcapng shared object Example:
+//! Without the `static` feature libpcapng-rs dynamically links the system's libpcapng shared
+//! object, found through pkg-config or the usual library directories:
 //!
 //! ```toml
 //! [dependencies]
-//! libpcapng_rs = { version = "0.1", default-features = false }
+//! libpcapng_rs = "0.1"
+//! ```
+//!
+//! Earlier releases enabled `static` by default, builds relying on that must now enable it and
+//! set `LIBPCAPNG_SRC_DIR`.
+//!
+//! The `pure-rust` feature replaces libpcapng with a native implementation of the same API which
+//! writes the same bytes, so nothing but the Rust toolchain is needed to build:
+//!
+//! ```toml
+//! [dependencies]
+//! libpcapng_rs = { version = "0.1", default-features = false, features = ["pure-rust"] }
 //! ```
 //! ## Features
+//! `libpcapng` enabled by default, this feature reads and writes through the libpcapng c library, linked dynamically unless `static` is enabled
+//! `pure-rust` this feature reads and writes natively without the libpcapng c library, taking precedence over `libpcapng`
 //! `static` this feature statically compiles libpcapng c library in to the crate
 //! `macos` this feature enables building on macos as opposed to linux as the native libc interfaces are a bit different
 //!
@@ -50,8 +80,9 @@
 //!
 //! ### Example Code
 //! ```rust
-//! use libpcapng_rs::{PcapNg, PcapNgOpenMode};
+//! use libpcapng_rs::{BlockInfo, PcapNg, PcapNgOpenMode};
 //! use std::fs;
+//! use std::ops::ControlFlow;
 //!
 //! fn main() {
 //!     let mut pcap_writer = PcapNg::new("test.pcapng", PcapNgOpenMode::Write);
@@ -60,14 +91,15 @@
 //!     pcap_writer.close();
 //!     let mut pcap_writer = PcapNg::new("test.pcapng", PcapNgOpenMode::Read);
 //!     pcap_writer.open().expect("issue opening file");
-//!     pcap_writer.read_packets(Some(callback_rs)).unwrap();
+//!     pcap_writer.read_packets(callback_rs).unwrap();
 //!     pcap_writer.close();
 //!     fs::remove_file("test.pcapng").unwrap();
 //! }
 //!
-//! fn callback_rs(block_counter: u32, block_type: u32, block_total_length: u32, bytes: Vec<u8>) {
+//! fn callback_rs(info: BlockInfo, bytes: &[u8]) -> ControlFlow<String> {
 //!     println!("hello world");
-//!     println!("block_counter: {}, block_type: {}, block_total_length: {} bytes {:02X?}", block_counter, block_type, block_total_length, bytes);
+//!     println!("block_counter: {}, block_type: {}, block_total_length: {} bytes {:02X?}", info.block_counter, info.block_type, info.block_total_length, bytes);
+//!     ControlFlow::Continue(())
 //! }
 //! ```
 //!
@@ -87,22 +119,73 @@
 #![allow(clippy::type_complexity)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(not(any(feature = "libpcapng", feature = "pure-rust")))]
+compile_error!("either the `libpcapng` or the `pure-rust` feature must be enabled");
+
+mod block;
+mod constants;
+mod custom;
+#[cfg(feature = "serde")]
+mod custom_serde;
 mod error;
+mod interface;
+mod key_log;
+#[cfg(feature = "libpcapng")]
+#[cfg_attr(feature = "pure-rust", allow(dead_code))]
+mod libpcapng_backend;
+mod name_resolution;
+mod option;
+mod packet;
 mod pcapng;
+mod reader;
+#[cfg(any(feature = "pure-rust", test))]
+mod rust_backend;
+mod secrets;
+mod section;
+mod statistics;
+#[cfg(all(test, not(feature = "pure-rust")))]
+mod test_backend;
+mod timestamp;
 
+#[cfg(all(not(test), not(feature = "pure-rust")))]
+use libpcapng_backend as backend;
+#[cfg(all(test, not(feature = "pure-rust")))]
+use test_backend as backend;
+#[cfg(feature = "pure-rust")]
+use rust_backend as backend;
+
+pub use block::*;
+pub use constants::*;
+pub use custom::*;
+pub use interface::*;
+pub use key_log::*;
+pub use option::*;
+pub use packet::*;
 pub use pcapng::*;
+pub use reader::*;
+pub use secrets::*;
+pub use section::*;
+pub use statistics::*;
+pub use timestamp::*;
 
 pub use error::*;
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::fs;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::ops::ControlFlow;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{PCAPNG_CUSTOM_DATA_BLOCK, PCAPNG_ENHANCED_PACKET_BLOCK, PCAPNG_INTERFACE_DESCRIPTION_BLOCK, PCAPNG_SECTION_HEADER_BLOCK};
 
-    use crate::{PcapNg, PcapNgOpenMode};
+    use crate::{extract_key_logs, inject_key_log, Block, BlockInfo, BlockOption, CustomBlock, CustomBlockCodec, CustomBlockRegistry, CustomValue, EnhancedPacket, Interface, InterfaceDescription, NameResolution, PacketDirection, PacketHash, PacketOptions, PacketVerdict, PcapNg, PcapNgError, PcapNgOpenMode, RawBlock, SecretsKind, Section, SectionHeader, SimplePacket, Statistics, TsResolution, LINKTYPE_ETHERNET, LINKTYPE_IEEE802_11_RADIOTAP, LINKTYPE_LINUX_SLL2};
 
-    fn callback_rs(block_counter: u32, block_type: u32, block_total_length: u32, bytes: Vec<u8>) {
+    fn callback_rs(info: BlockInfo, bytes: &[u8]) -> ControlFlow<String> {
         println!("hello world");
-        println!("block_counter: {}, block_type: {}, block_total_length: {} bytes {:02X?}", block_counter, block_type, block_total_length, bytes);
+        println!("block_counter: {}, block_type: {}, block_total_length: {} bytes {:02X?}", info.block_counter, info.block_type, info.block_total_length, bytes);
+        ControlFlow::Continue(())
     }
 
     #[test]
@@ -113,8 +196,963 @@ mod tests {
         pcap_writer.close();
         let mut pcap_writer = PcapNg::new("test.pcapng", PcapNgOpenMode::Read);
         pcap_writer.open().expect("issue opening file");
-        pcap_writer.read_packets(Some(callback_rs)).unwrap();
+        pcap_writer.read_packets(callback_rs).unwrap();
         pcap_writer.close();
         fs::remove_file("test.pcapng").unwrap();
     }
+
+    #[test]
+    fn blocks_iterator_test() {
+        let mut pcap_writer = PcapNg::new("blocks.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_custom("this is a test".as_bytes().to_vec()).expect("issue writing custom frame");
+        pcap_writer.close();
+        let mut pcap_reader = PcapNg::new("blocks.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let block_types: Vec<u32> = pcap_reader.raw_blocks().unwrap().map(|b| b.unwrap().block_type).collect();
+        assert_eq!(block_types, vec![PCAPNG_SECTION_HEADER_BLOCK, PCAPNG_INTERFACE_DESCRIPTION_BLOCK, PCAPNG_CUSTOM_DATA_BLOCK]);
+        pcap_reader.close();
+
+        let mut pcap_reader = PcapNg::new("blocks.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let first = pcap_reader.raw_blocks().unwrap().next().unwrap().unwrap();
+        assert_eq!(first.block_counter, 1);
+        assert_eq!(&first.body()[0..4], &[0x4D, 0x3C, 0x2B, 0x1A]);
+        let next = pcap_reader.raw_blocks().unwrap().next().unwrap().unwrap();
+        assert_eq!(next.block_type, PCAPNG_INTERFACE_DESCRIPTION_BLOCK);
+        assert_eq!(next.block_counter, 2);
+        pcap_reader.close();
+        fs::remove_file("blocks.pcapng").unwrap();
+    }
+
+    #[test]
+    fn read_packets_closure_test() {
+        let mut pcap_writer = PcapNg::new("closure.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_custom("first".as_bytes().to_vec()).expect("issue writing custom frame");
+        pcap_writer.write_custom("second".as_bytes().to_vec()).expect("issue writing custom frame");
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("closure.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let mut custom_lengths = Vec::new();
+        pcap_reader.read_packets(|info, bytes| {
+            if info.block_type == PCAPNG_CUSTOM_DATA_BLOCK {
+                custom_lengths.push(bytes.len());
+            }
+            ControlFlow::<String>::Continue(())
+        }).unwrap();
+        assert_eq!(custom_lengths, vec![16, 16]);
+        pcap_reader.close();
+
+        let mut pcap_reader = PcapNg::new("closure.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let mut seen = 0;
+        let result = pcap_reader.read_packets(|info, _| {
+            seen += 1;
+            if info.block_type == PCAPNG_CUSTOM_DATA_BLOCK {
+                ControlFlow::Break("stopped at custom block")
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert!(matches!(result, Err(PcapNgError::CallbackError(e)) if e.to_string() == "stopped at custom block"));
+        assert_eq!(seen, 3);
+        pcap_reader.close();
+        fs::remove_file("closure.pcapng").unwrap();
+    }
+
+    #[test]
+    fn decode_blocks_test() {
+        let mut pcap_writer = PcapNg::new("decode.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet(vec![0x45, 0, 0, 20, 1]).expect("issue writing packet");
+        pcap_writer.write_custom("this is a test".as_bytes().to_vec()).expect("issue writing custom frame");
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("decode.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let blocks: Vec<Block> = pcap_reader.blocks().unwrap().collect::<crate::Result<_>>().unwrap();
+        pcap_reader.close();
+        fs::remove_file("decode.pcapng").unwrap();
+
+        assert_eq!(blocks.len(), 4);
+        assert!(matches!(&blocks[0], Block::SectionHeader(shb) if shb.major_version == 1 && shb.minor_version == 0));
+        assert!(matches!(&blocks[1], Block::InterfaceDescription(_)));
+        match &blocks[2] {
+            Block::EnhancedPacket(epb) => {
+                assert_eq!(epb.interface_id, 0);
+                assert_eq!(epb.captured_len, 5);
+                assert_eq!(epb.original_len, 5);
+                assert_eq!(epb.data, vec![0x45, 0, 0, 20, 1]);
+            }
+            other => panic!("expected an enhanced packet, got {:?}", other),
+        }
+        assert_eq!(blocks[3], Block::Custom(CustomBlock {
+            copyable: true,
+            pen: crate::PCAPNG_PEN,
+            data: b"this is a test\0\0".to_vec(),
+        }));
+    }
+
+    fn round_trip(block: &Block) -> Block {
+        let bytes = block.encode().unwrap();
+        Block::decode(RawBlock {
+            block_counter: 1,
+            block_type: u32::from_ne_bytes(bytes[0..4].try_into().unwrap()),
+            block_total_length: bytes.len() as u32,
+            data: bytes[8..].to_vec(),
+        }).unwrap()
+    }
+
+    #[test]
+    fn block_options_round_trip_test() {
+        let shb = Block::SectionHeader(SectionHeader {
+            major_version: 1,
+            minor_version: 0,
+            section_length: -1,
+            options: vec![
+                BlockOption::Comment("collected by sensor 7".to_string()),
+                BlockOption::ShbHardware("x86_64".to_string()),
+                BlockOption::ShbUserAppl("libpcapng-rs".to_string()),
+            ],
+        });
+        assert_eq!(round_trip(&shb), shb);
+
+        let idb = Block::InterfaceDescription(InterfaceDescription {
+            link_type: 1,
+            snaplen: 65535,
+            options: vec![
+                BlockOption::IfName("eth0".to_string()),
+                BlockOption::IfTsResol(TsResolution::NANOSECONDS),
+                BlockOption::IfFilter { filter_type: 0, filter: b"tcp port 443".to_vec() },
+                BlockOption::IfTsOffset(-5),
+            ],
+        });
+        assert_eq!(round_trip(&idb), idb);
+
+        let epb = Block::EnhancedPacket(EnhancedPacket {
+            interface_id: 0,
+            timestamp_high: 1,
+            timestamp_low: 2,
+            captured_len: 3,
+            original_len: 3,
+            data: vec![1, 2, 3],
+            options: vec![
+                BlockOption::EpbFlags(1),
+                BlockOption::EpbHash { algorithm: 2, hash: vec![0xde, 0xad, 0xbe, 0xef] },
+                BlockOption::Custom { copyable: true, utf8: false, pen: 31337, data: vec![9] },
+                BlockOption::Unknown { code: 42, value: vec![1, 2, 3, 4, 5] },
+            ],
+            ..Default::default()
+        });
+        assert_eq!(round_trip(&epb), epb);
+    }
+
+    #[test]
+    fn parse_options_test() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&1u16.to_ne_bytes());
+        bytes.extend_from_slice(&5u16.to_ne_bytes());
+        bytes.extend_from_slice(b"hello\0\0\0");
+        bytes.extend_from_slice(&4u16.to_ne_bytes());
+        bytes.extend_from_slice(&8u16.to_ne_bytes());
+        bytes.extend_from_slice(&7u64.to_ne_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&[0xff; 8]);
+        let options = BlockOption::parse_all(PCAPNG_ENHANCED_PACKET_BLOCK, &bytes).unwrap();
+        assert_eq!(options, vec![BlockOption::Comment("hello".to_string()), BlockOption::EpbDropCount(7)]);
+
+        let mut encoded = Vec::new();
+        BlockOption::encode_all(&options, &mut encoded).unwrap();
+        assert_eq!(encoded, bytes[..bytes.len() - 8]);
+    }
+
+    #[test]
+    fn write_interfaces_test() {
+        let mut pcap_writer = PcapNg::new("interfaces.pcapng", PcapNgOpenMode::Write);
+        let eth = pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).snaplen(128).name("eth0").os("Linux")).unwrap();
+        let wlan = pcap_writer.add_interface(Interface::new(LINKTYPE_IEEE802_11_RADIOTAP).description("monitor")).unwrap();
+        assert_eq!((eth, wlan), (0, 1));
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet(vec![0xff; 14]).expect("issue writing packet");
+        assert_eq!(pcap_writer.add_interface(Interface::new(LINKTYPE_LINUX_SLL2)).unwrap(), 2);
+        pcap_writer.close();
+
+        let mut pcap_appender = PcapNg::new("interfaces.pcapng", PcapNgOpenMode::Append);
+        assert!(matches!(pcap_appender.add_interface(Interface::new(LINKTYPE_ETHERNET)), Err(PcapNgError::FileNotOpen)));
+        pcap_appender.open().expect("issue opening file");
+        assert_eq!(pcap_appender.interfaces().len(), 3);
+        assert_eq!(pcap_appender.add_interface(Interface::new(LINKTYPE_ETHERNET)).unwrap(), 3);
+        pcap_appender.close();
+
+        let mut pcap_reader = PcapNg::new("interfaces.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let blocks: Vec<Block> = pcap_reader.blocks().unwrap().collect::<crate::Result<_>>().unwrap();
+        pcap_reader.close();
+        fs::remove_file("interfaces.pcapng").unwrap();
+
+        assert_eq!(blocks.len(), 6);
+        assert_eq!(blocks[1], Block::InterfaceDescription(InterfaceDescription {
+            link_type: LINKTYPE_ETHERNET,
+            snaplen: 128,
+            options: vec![BlockOption::IfName("eth0".to_string()), BlockOption::IfOs("Linux".to_string())],
+        }));
+        assert!(matches!(&blocks[2], Block::InterfaceDescription(idb) if idb.link_type == LINKTYPE_IEEE802_11_RADIOTAP));
+        assert!(matches!(&blocks[3], Block::EnhancedPacket(_)));
+        assert!(matches!(&blocks[4], Block::InterfaceDescription(idb) if idb.link_type == LINKTYPE_LINUX_SLL2));
+        assert!(matches!(&blocks[5], Block::InterfaceDescription(idb) if idb.link_type == LINKTYPE_ETHERNET));
+    }
+
+    #[test]
+    fn write_packet_on_interface_test() {
+        let mut pcap_writer = PcapNg::new("multi.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).name("eth0")).unwrap();
+        let tunnel = pcap_writer.add_interface(Interface::new(crate::LINKTYPE_RAW).name("tun0")).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet_on(tunnel, vec![0x45, 0, 0, 20]).expect("issue writing packet");
+        pcap_writer.write_packet_on(0, vec![0xff; 14]).expect("issue writing packet");
+        assert!(matches!(pcap_writer.write_packet_on(2, vec![0x45]), Err(PcapNgError::UnknownInterface(2))));
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("multi.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let interface_ids: Vec<u32> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::EnhancedPacket(epb) => Some(epb.interface_id),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("multi.pcapng").unwrap();
+        assert_eq!(interface_ids, vec![1, 0]);
+    }
+
+    #[test]
+    fn write_packet_with_time_test() {
+        let mut pcap_writer = PcapNg::new("timestamps.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET)).unwrap();
+        let nanos = pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).ts_resolution(TsResolution::NANOSECONDS)).unwrap();
+        let binary = pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).ts_resolution(TsResolution::Binary(10))).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        let time = Duration::new(1_700_000_000, 123_456_789);
+        pcap_writer.write_packet_with_time(vec![1], time).unwrap();
+        pcap_writer.write_packet_on_with_time(nanos, vec![2], UNIX_EPOCH + time).unwrap();
+        pcap_writer.write_packet_on_with_time(binary, vec![3], time).unwrap();
+        pcap_writer.write_packet_on_with_time(nanos, vec![4], (1_700_000_000_123_456u64, TsResolution::MICROSECONDS)).unwrap();
+        pcap_writer.write_packet_with_time(vec![5], 1_700_000_000u32).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("timestamps.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let timestamps: Vec<u64> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::EnhancedPacket(epb) => Some((epb.timestamp_high as u64) << 32 | epb.timestamp_low as u64),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("timestamps.pcapng").unwrap();
+        assert_eq!(timestamps, vec![
+            1_700_000_000_123_456,
+            1_700_000_000_123_456_789,
+            1_700_000_000 * 1024 + 126,
+            1_700_000_000_123_456_000,
+            1_700_000_000_000_000,
+        ]);
+    }
+
+    #[test]
+    fn read_packet_timestamp_test() {
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let mut pcap_writer = PcapNg::new("read_timestamps.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).ts_resolution(TsResolution::NANOSECONDS)).unwrap();
+        pcap_writer.add_interface(InterfaceDescription {
+            link_type: LINKTYPE_ETHERNET,
+            snaplen: 0,
+            options: vec![BlockOption::IfTsResol(TsResolution::Binary(20)), BlockOption::IfTsOffset(1_600_000_000)],
+        }).unwrap();
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET)).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet_on_with_time(0, vec![1], time).unwrap();
+        pcap_writer.write_packet_on_with_time(1, vec![2], time).unwrap();
+        pcap_writer.write_packet_on_with_time(2, vec![3], time).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("read_timestamps.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let packets: Vec<EnhancedPacket> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::EnhancedPacket(epb) => Some(epb),
+            _ => None,
+        }).collect();
+        assert_eq!(pcap_reader.interfaces().len(), 3);
+        pcap_reader.close();
+        fs::remove_file("read_timestamps.pcapng").unwrap();
+
+        assert_eq!(packets[0].timestamp(), time);
+        assert_eq!(packets[1].ts_offset, 1_600_000_000);
+        let error = packets[1].timestamp().duration_since(time).unwrap_or_else(|e| e.duration());
+        assert!(error < Duration::from_micros(1));
+        assert_eq!(packets[2].timestamp(), time - Duration::from_nanos(789));
+    }
+
+    #[test]
+    fn write_packet_with_options_test() {
+        let mut options = PacketOptions {
+            comments: vec!["suspicious beacon".to_string()],
+            hash: Some(PacketHash { algorithm: 2, hash: vec![0xde, 0xad, 0xbe, 0xef] }),
+            drop_count: Some(3),
+            packet_id: Some(0x1122334455667788),
+            queue: Some(4),
+            verdict: Some(PacketVerdict { verdict_type: 2, verdict: vec![1, 0, 0, 0, 0, 0, 0, 0] }),
+            ..Default::default()
+        };
+        options.set_direction(PacketDirection::Outbound);
+        assert_eq!(options.flags, Some(2));
+
+        let mut pcap_writer = PcapNg::new("packet_options.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet_with_options(0, vec![0x45, 0, 0, 20], Duration::from_secs(1), &options).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("packet_options.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let read: Vec<PacketOptions> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::EnhancedPacket(epb) => Some(epb.packet_options()),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("packet_options.pcapng").unwrap();
+        assert_eq!(read, vec![options]);
+        assert_eq!(read[0].direction(), PacketDirection::Outbound);
+    }
+
+    #[test]
+    fn write_truncated_packet_test() {
+        let mut pcap_writer = PcapNg::new("truncated.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).snaplen(4)).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_truncated_packet(0, vec![1, 2], 1500, Duration::from_secs(1), &PacketOptions::default()).unwrap();
+        assert!(matches!(
+            pcap_writer.write_truncated_packet(0, vec![1, 2], 1, Duration::from_secs(1), &PacketOptions::default()),
+            Err(PcapNgError::OriginalLengthTooShort(1, 2))
+        ));
+        pcap_writer.write_packet(vec![1, 2, 3, 4, 5, 6]).unwrap();
+        pcap_writer.set_truncate_to_snaplen(true);
+        pcap_writer.write_packet(vec![1, 2, 3, 4, 5, 6]).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("truncated.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let lengths: Vec<(u32, u32, usize)> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::EnhancedPacket(epb) => Some((epb.captured_len, epb.original_len, epb.data.len())),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("truncated.pcapng").unwrap();
+        assert_eq!(lengths, vec![(2, 1500, 2), (6, 6, 6), (4, 6, 4)]);
+    }
+
+    #[test]
+    fn section_header_options_test() {
+        let mut pcap_writer = PcapNg::new("section.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.set_section_header(Section::new()
+            .hardware("x86_64")
+            .os("Linux 6.1")
+            .user_application("collector 2.3")
+            .comment("rack 12")
+            .comment("bucket 4")).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        assert!(matches!(pcap_writer.set_section_header(Section::new()), Err(PcapNgError::SectionHeaderAlreadyWritten)));
+        pcap_writer.write_packet(vec![0x45, 0, 0, 20]).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("section.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let blocks: Vec<Block> = pcap_reader.blocks().unwrap().collect::<crate::Result<_>>().unwrap();
+        pcap_reader.close();
+        fs::remove_file("section.pcapng").unwrap();
+
+        assert_eq!(blocks.len(), 3);
+        match &blocks[0] {
+            Block::SectionHeader(shb) => {
+                assert_eq!(shb.hardware(), Some("x86_64"));
+                assert_eq!(shb.os(), Some("Linux 6.1"));
+                assert_eq!(shb.user_application(), Some("collector 2.3"));
+                assert_eq!(shb.comments(), vec!["rack 12", "bucket 4"]);
+            }
+            other => panic!("expected a section header, got {:?}", other),
+        }
+        assert!(matches!(&blocks[1], Block::InterfaceDescription(idb) if idb.link_type == crate::LINKTYPE_RAW));
+    }
+
+    #[test]
+    fn name_resolution_test() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let mut pcap_writer = PcapNg::new("names.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_name_resolution(HashMap::from([(v4, vec!["www.example.com".to_string()])])).unwrap();
+        let mut name_resolution = NameResolution::default();
+        name_resolution.push(v4, vec!["example.com".to_string(), "www.example.com".to_string()]);
+        name_resolution.push(v6, vec!["v6.example.com".to_string()]);
+        name_resolution.options.push(BlockOption::NsDnsName("ns1.example.com".to_string()));
+        name_resolution.options.push(BlockOption::NsDnsIp4Addr(Ipv4Addr::new(192, 0, 2, 53)));
+        pcap_writer.write_name_resolution(name_resolution.clone()).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("names.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let names = pcap_reader.read_name_resolution().unwrap();
+        pcap_reader.close();
+        assert_eq!(names, HashMap::from([
+            (v4, vec!["www.example.com".to_string(), "example.com".to_string()]),
+            (v6, vec!["v6.example.com".to_string()]),
+        ]));
+
+        let mut pcap_reader = PcapNg::new("names.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let read = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::NameResolution(nrb) => Some(nrb),
+            _ => None,
+        }).last().unwrap();
+        pcap_reader.close();
+        fs::remove_file("names.pcapng").unwrap();
+        assert_eq!(read, name_resolution);
+        assert_eq!(read.dns_name(), Some("ns1.example.com"));
+        assert_eq!(read.dns_ipv4_address(), Some(Ipv4Addr::new(192, 0, 2, 53)));
+    }
+
+    #[test]
+    fn interface_statistics_test() {
+        let start = UNIX_EPOCH + Duration::new(1_700_000_000, 500_000_000);
+        let mut pcap_writer = PcapNg::new("statistics.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).ts_resolution(TsResolution::NANOSECONDS)).unwrap();
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET)).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        assert!(matches!(pcap_writer.write_interface_statistics(2, &Statistics::default()), Err(PcapNgError::UnknownInterface(2))));
+        pcap_writer.close_with_statistics(|interface_id| Statistics {
+            start_time: Some(start),
+            end_time: Some(start + Duration::from_secs(60)),
+            if_recv: Some(100 + interface_id as u64),
+            if_drop: Some(interface_id as u64),
+            os_drop: Some(7),
+            ..Default::default()
+        }).unwrap();
+
+        let mut pcap_reader = PcapNg::new("statistics.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let statistics: Vec<(u32, Statistics)> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::InterfaceStatistics(isb) => Some((isb.interface_id, isb.statistics())),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("statistics.pcapng").unwrap();
+
+        assert_eq!(statistics.len(), 2);
+        assert_eq!(statistics[0], (0, Statistics {
+            start_time: Some(start),
+            end_time: Some(start + Duration::from_secs(60)),
+            if_recv: Some(100),
+            if_drop: Some(0),
+            os_drop: Some(7),
+            ..Default::default()
+        }));
+        assert_eq!(statistics[1].0, 1);
+        assert_eq!(statistics[1].1.if_recv, Some(101));
+        assert_eq!(statistics[1].1.start_time, Some(start));
+    }
+
+    #[test]
+    fn decryption_secrets_test() {
+        let key_log = b"CLIENT_RANDOM 0123 4567\n".to_vec();
+        let mut pcap_writer = PcapNg::new("decryption_secrets.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_decryption_secrets(SecretsKind::TlsKeyLog, key_log.clone()).unwrap();
+        pcap_writer.write_decryption_secrets(SecretsKind::ZigBeeNwkKey, vec![0xAB; 16]).unwrap();
+        pcap_writer.write_decryption_secrets(SecretsKind::Other(0x1234), vec![1, 2, 3]).unwrap();
+        pcap_writer.write_packet(vec![1, 2, 3, 4]).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("decryption_secrets.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let secrets = pcap_reader.read_decryption_secrets().unwrap();
+        pcap_reader.close();
+        fs::remove_file("decryption_secrets.pcapng").unwrap();
+
+        assert_eq!(secrets, vec![
+            (SecretsKind::TlsKeyLog, key_log),
+            (SecretsKind::ZigBeeNwkKey, vec![0xAB; 16]),
+            (SecretsKind::Other(0x1234), vec![1, 2, 3]),
+        ]);
+        assert_eq!(u32::from(SecretsKind::WireGuardKeyLog), 0x5747_4B4C);
+    }
+
+    #[test]
+    fn inject_key_log_test() {
+        let key_log = format!("# TLS secrets\nCLIENT_RANDOM {} {}\nCLIENT_TRAFFIC_SECRET_0 {} {}\nECH_SECRET {} {}\n", "ab".repeat(32), "cd".repeat(48), "ef".repeat(32), "01".repeat(32), "ab".repeat(32), "23".repeat(20));
+        fs::write("inject_key_log.keys", &key_log).unwrap();
+        let mut pcap_writer = PcapNg::new("inject_key_log.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_name_resolution(HashMap::from([(IpAddr::V4(Ipv4Addr::LOCALHOST), vec!["localhost".to_string()])])).unwrap();
+        pcap_writer.write_packet(vec![1, 2, 3, 4]).unwrap();
+        pcap_writer.write_packet(vec![5, 6, 7, 8]).unwrap();
+        pcap_writer.close();
+
+        inject_key_log("inject_key_log.pcapng", "inject_key_log.keys", "inject_key_log_out.pcapng").unwrap();
+        let mut pcap_reader = PcapNg::new("inject_key_log_out.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let blocks: Vec<Block> = pcap_reader.blocks().unwrap().map(|block| block.unwrap()).collect();
+        pcap_reader.close();
+
+        assert_eq!(blocks.len(), 6);
+        assert!(matches!(blocks[2], Block::NameResolution(_)));
+        match &blocks[3] {
+            Block::DecryptionSecrets(dsb) => {
+                assert_eq!(dsb.kind(), SecretsKind::TlsKeyLog);
+                assert_eq!(dsb.data, key_log.as_bytes());
+            }
+            block => panic!("expected decryption secrets, got {:?}", block),
+        }
+        assert!(matches!(&blocks[4], Block::EnhancedPacket(packet) if packet.data == vec![1, 2, 3, 4]));
+
+        fs::write("inject_key_log.keys", format!("CLIENT_RANDOM {} {}\nCLIENT_RANDOM zz\n", "ab".repeat(32), "cd".repeat(48))).unwrap();
+        let result = inject_key_log("inject_key_log.pcapng", "inject_key_log.keys", "inject_key_log_out.pcapng");
+        assert!(matches!(result, Err(PcapNgError::MalformedKeyLog(2, _))));
+        fs::write("inject_key_log.keys", format!("ECH_CONFIG {} abc\n", "ab".repeat(32))).unwrap();
+        let result = inject_key_log("inject_key_log.pcapng", "inject_key_log.keys", "inject_key_log_out.pcapng");
+        assert!(matches!(result, Err(PcapNgError::MalformedKeyLog(1, _))));
+
+        fs::write("inject_key_log.keys", &key_log).unwrap();
+        let capture = fs::read("inject_key_log.pcapng").unwrap();
+        let result = inject_key_log("inject_key_log.pcapng", "inject_key_log.keys", "./inject_key_log.pcapng");
+        assert!(matches!(result, Err(PcapNgError::OutputIsInput)));
+        assert_eq!(fs::read("inject_key_log.pcapng").unwrap(), capture);
+        fs::write("inject_key_log_empty.pcapng", []).unwrap();
+        let result = inject_key_log("inject_key_log_empty.pcapng", "inject_key_log.keys", "inject_key_log_empty_out.pcapng");
+        assert!(matches!(result, Err(PcapNgError::MalformedBlock(_))));
+        assert!(!std::path::Path::new("inject_key_log_empty_out.pcapng").exists());
+
+        fs::remove_file("inject_key_log_empty.pcapng").unwrap();
+        fs::remove_file("inject_key_log.keys").unwrap();
+        fs::remove_file("inject_key_log.pcapng").unwrap();
+        fs::remove_file("inject_key_log_out.pcapng").unwrap();
+    }
+
+    #[test]
+    fn extract_key_logs_test() {
+        let directory = std::env::temp_dir().join("libpcapng_extract_key_logs");
+        fs::create_dir_all(&directory).unwrap();
+        let wireguard = "LOCAL_STATIC_PRIVATE_KEY = AAAA\n".to_string();
+        let mut pcap_writer = PcapNg::new("extract_key_logs.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_decryption_secrets(SecretsKind::TlsKeyLog, b"CLIENT_RANDOM a b".to_vec()).unwrap();
+        pcap_writer.write_decryption_secrets(SecretsKind::WireGuardKeyLog, wireguard.clone().into_bytes()).unwrap();
+        pcap_writer.write_packet(vec![1, 2, 3, 4]).unwrap();
+        pcap_writer.write_decryption_secrets(SecretsKind::TlsKeyLog, b"CLIENT_RANDOM c d\n".to_vec()).unwrap();
+        pcap_writer.close();
+
+        let files = extract_key_logs("extract_key_logs.pcapng", &directory).unwrap();
+        fs::remove_file("extract_key_logs.pcapng").unwrap();
+        assert_eq!(files, vec![
+            (SecretsKind::TlsKeyLog, directory.join("tls.keys")),
+            (SecretsKind::WireGuardKeyLog, directory.join("wireguard.keys")),
+        ]);
+        assert_eq!(fs::read_to_string(directory.join("tls.keys")).unwrap(), "CLIENT_RANDOM a b\nCLIENT_RANDOM c d\n");
+        assert_eq!(fs::read_to_string(directory.join("wireguard.keys")).unwrap(), wireguard);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn custom_with_pen_test() {
+        let mut pcap_writer = PcapNg::new("custom_with_pen.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_custom_with_pen(32473, true, vec![1, 2, 3, 4]).unwrap();
+        pcap_writer.write_custom_with_pen(12345, false, "hello".as_bytes().to_vec()).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("custom_with_pen.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let custom_blocks = pcap_reader.read_custom().unwrap();
+        pcap_reader.close();
+        fs::remove_file("custom_with_pen.pcapng").unwrap();
+
+        assert_eq!(custom_blocks, vec![
+            CustomBlock { copyable: true, pen: 32473, data: vec![1, 2, 3, 4] },
+            CustomBlock { copyable: false, pen: 12345, data: vec![b'h', b'e', b'l', b'l', b'o', 0, 0, 0] },
+        ]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Metadata {
+        Sensor(String),
+        Score(u32),
+    }
+
+    struct SensorCodec;
+
+    impl CustomBlockCodec<Metadata> for SensorCodec {
+        fn pen(&self) -> u32 {
+            1000
+        }
+
+        fn decode(&self, data: &[u8]) -> Result<Metadata, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Metadata::Sensor(String::from_utf8(data.to_vec())?.trim_end_matches('\0').to_string()))
+        }
+
+        fn encode(&self, value: &Metadata) -> Vec<u8> {
+            match value {
+                Metadata::Sensor(name) => name.as_bytes().to_vec(),
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    struct ScoreCodec;
+
+    impl CustomBlockCodec<Metadata> for ScoreCodec {
+        fn pen(&self) -> u32 {
+            2000
+        }
+
+        fn decode(&self, data: &[u8]) -> Result<Metadata, Box<dyn std::error::Error + Send + Sync>> {
+            let bytes = data.get(..4).ok_or("score too short")?;
+            Ok(Metadata::Score(u32::from_ne_bytes(bytes.try_into()?)))
+        }
+
+        fn encode(&self, value: &Metadata) -> Vec<u8> {
+            match value {
+                Metadata::Score(score) => score.to_ne_bytes().to_vec(),
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_codec_registry_test() {
+        let mut pcap_writer = PcapNg::new("custom_codec_registry.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_custom_value(&SensorCodec, &Metadata::Sensor("edge-1".to_string())).unwrap();
+        pcap_writer.write_custom_value(&ScoreCodec, &Metadata::Score(87)).unwrap();
+        pcap_writer.write_custom_with_pen(3000, false, vec![1, 2, 3, 4]).unwrap();
+        pcap_writer.write_custom_with_pen(1000, true, vec![0xFF]).unwrap();
+        pcap_writer.close();
+
+        let registry = CustomBlockRegistry::new().register(SensorCodec).register(ScoreCodec);
+        let mut pcap_reader = PcapNg::new("custom_codec_registry.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let values: Vec<CustomValue<Metadata>> = pcap_reader.blocks().unwrap()
+            .filter_map(|block| match block.unwrap() {
+                Block::Custom(custom) => Some(registry.decode(custom)),
+                _ => None,
+            })
+            .take(3)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        pcap_reader.close();
+        assert_eq!(values, vec![
+            CustomValue::Decoded { pen: 1000, value: Metadata::Sensor("edge-1".to_string()) },
+            CustomValue::Decoded { pen: 2000, value: Metadata::Score(87) },
+            CustomValue::Raw(CustomBlock { copyable: false, pen: 3000, data: vec![1, 2, 3, 4] }),
+        ]);
+
+        pcap_reader.open().expect("issue opening file");
+        let result = pcap_reader.read_custom_values(&registry);
+        pcap_reader.close();
+        fs::remove_file("custom_codec_registry.pcapng").unwrap();
+        assert!(matches!(result, Err(PcapNgError::CustomCodecError(1000, _))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Detection {
+        rule: String,
+        severity: u8,
+        flows: Vec<u32>,
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn custom_serde_test() {
+        let detections = vec![
+            Detection { rule: "ET SCAN".to_string(), severity: 2, flows: vec![1, 5] },
+            Detection { rule: "ET POLICY".to_string(), severity: 1, flows: Vec::new() },
+        ];
+        let mut pcap_writer = PcapNg::new("custom_serde.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_custom_serde(32473, &detections[0]).unwrap();
+        pcap_writer.write_custom_with_pen(1000, true, vec![1, 2, 3]).unwrap();
+        pcap_writer.write_custom_serde(32473, &detections[1]).unwrap();
+        pcap_writer.write_custom_serde(2000, &"not a detection").unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("custom_serde.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        assert_eq!(pcap_reader.read_custom_serde::<Detection>(32473).unwrap(), detections);
+        pcap_reader.close();
+        pcap_reader.open().expect("issue opening file");
+        assert!(matches!(pcap_reader.read_custom_serde::<Detection>(2000), Err(PcapNgError::CustomCodecError(2000, _))));
+        pcap_reader.close();
+        fs::remove_file("custom_serde.pcapng").unwrap();
+    }
+
+    #[test]
+    fn simple_packet_test() {
+        let mut pcap_writer = PcapNg::new("simple_packet.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).snaplen(6)).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_simple_packet(vec![1, 2, 3, 4, 5]).unwrap();
+        pcap_writer.write_simple_packet(vec![1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("simple_packet.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let packets: Vec<SimplePacket> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::SimplePacket(packet) => Some(packet),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("simple_packet.pcapng").unwrap();
+
+        assert_eq!(packets, vec![
+            SimplePacket { original_len: 5, data: vec![1, 2, 3, 4, 5] },
+            SimplePacket { original_len: 8, data: vec![1, 2, 3, 4, 5, 6] },
+        ]);
+
+        // a snaplen the writer ignored still limits the captured length on read
+        let mut pcap_writer = PcapNg::new("simple_packet_snaplen.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).snaplen(2)).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_bytes(&Block::SimplePacket(SimplePacket { original_len: 4, data: vec![1, 2, 3, 4] }).encode().unwrap()).unwrap();
+        pcap_writer.close();
+        let mut pcap_reader = PcapNg::new("simple_packet_snaplen.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let last = pcap_reader.blocks().unwrap().last().unwrap().unwrap();
+        pcap_reader.close();
+        fs::remove_file("simple_packet_snaplen.pcapng").unwrap();
+        assert_eq!(last, Block::SimplePacket(SimplePacket { original_len: 4, data: vec![1, 2] }));
+    }
+
+    #[test]
+    fn obsolete_packet_block_test() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&1u16.to_ne_bytes());
+        body.extend_from_slice(&3u16.to_ne_bytes());
+        body.extend_from_slice(&0u32.to_ne_bytes());
+        body.extend_from_slice(&1_500_000u32.to_ne_bytes());
+        body.extend_from_slice(&3u32.to_ne_bytes());
+        body.extend_from_slice(&60u32.to_ne_bytes());
+        body.extend_from_slice(&[7, 8, 9, 0]);
+        BlockOption::encode_all(&[BlockOption::EpbFlags(1)], &mut body).unwrap();
+        let mut legacy = body.clone();
+        legacy[2..4].copy_from_slice(&u16::MAX.to_ne_bytes());
+        let packet_block = |body: Vec<u8>| {
+            let mut data = body;
+            data.extend_from_slice(&(data.len() as u32 + 12).to_ne_bytes());
+            Block::Unknown(RawBlock { block_counter: 0, block_type: 2, block_total_length: data.len() as u32 + 8, data })
+        };
+
+        let mut pcap_writer = PcapNg::new("obsolete_packet_block.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET)).unwrap();
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).ts_resolution(TsResolution::Decimal(3))).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_bytes(&packet_block(body).encode().unwrap()).unwrap();
+        pcap_writer.write_bytes(&packet_block(legacy).encode().unwrap()).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("obsolete_packet_block.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let packets: Vec<EnhancedPacket> = pcap_reader.blocks().unwrap().filter_map(|block| match block.unwrap() {
+            Block::EnhancedPacket(packet) => Some(packet),
+            _ => None,
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file("obsolete_packet_block.pcapng").unwrap();
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].interface_id, 1);
+        assert_eq!(packets[0].data, vec![7, 8, 9]);
+        assert_eq!(packets[0].original_len, 60);
+        assert_eq!(packets[0].timestamp(), UNIX_EPOCH + Duration::from_secs(1500));
+        assert_eq!(packets[0].options, vec![BlockOption::EpbFlags(1), BlockOption::EpbDropCount(3)]);
+        assert_eq!(packets[0].packet_options().drop_count, Some(3));
+        assert_eq!(packets[1].options, vec![BlockOption::EpbFlags(1)]);
+    }
+
+    #[cfg(feature = "libpcapng")]
+    #[test]
+    fn constants_match_libpcapng_test() {
+        assert_eq!(crate::PCAPNG_PEN, libpcapng_sys::PCAPNG_PEN);
+        assert_eq!(crate::PCAPNG_BYTE_ORDER_MAGIC, libpcapng_sys::PCAPNG_BYTE_ORDER_MAGIC);
+        assert_eq!(crate::PCAPNG_SECTION_HEADER_BLOCK, libpcapng_sys::PCAPNG_SECTION_HEADER_BLOCK);
+        assert_eq!(crate::PCAPNG_INTERFACE_DESCRIPTION_BLOCK, libpcapng_sys::PCAPNG_INTERFACE_DESCRIPTION_BLOCK);
+        assert_eq!(crate::PCAPNG_PACKET_BLOCK, libpcapng_sys::PCAPNG_PACKET_BLOCK);
+        assert_eq!(crate::PCAPNG_SIMPLE_PACKET_BLOCK, libpcapng_sys::PCAPNG_SIMPLE_PACKET_BLOCK);
+        assert_eq!(crate::PCAPNG_NAME_RESOLUTION_BLOCK, libpcapng_sys::PCAPNG_NAME_RESOLUTION_BLOCK);
+        assert_eq!(crate::PCAPNG_INTERFACE_STATISTICS_BLOCK, libpcapng_sys::PCAPNG_INTERFACE_STATISTICS_BLOCK);
+        assert_eq!(crate::PCAPNG_ENHANCED_PACKET_BLOCK, libpcapng_sys::PCAPNG_ENHANCED_PACKET_BLOCK);
+        assert_eq!(crate::PCAPNG_DECRYPTION_SECRETS_BLOCK, libpcapng_sys::PCAPNG_DECRYPTION_SECRETS_BLOCK);
+        assert_eq!(crate::PCAPNG_CUSTOM_DATA_BLOCK, libpcapng_sys::PCAPNG_CUSTOM_DATA_BLOCK);
+        assert_eq!(crate::PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY, libpcapng_sys::PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY);
+        assert_eq!(crate::PCAPNG_TLS_KEY_LOG, libpcapng_sys::PCAPNG_TLS_KEY_LOG);
+        assert_eq!(crate::PCAPNG_WIREGUARD_KEY_LOG, libpcapng_sys::PCAPNG_WIREGUARD_KEY_LOG);
+        assert_eq!(crate::PCAPNG_ZIGBEE_NWK_KEY, libpcapng_sys::PCAPNG_ZIGBEE_NWK_KEY);
+        assert_eq!(crate::PCAPNG_ZIGBEE_APS_KEY, libpcapng_sys::PCAPNG_ZIGBEE_APS_KEY);
+    }
+
+    /// Writes the same capture through whichever backend is selected, returning its blocks with
+    /// the interface statistics timestamp cleared as it is taken from the current time
+    #[cfg(not(feature = "pure-rust"))]
+    fn write_backend_capture(path: &str) -> Vec<RawBlock> {
+        let mut pcap_writer = PcapNg::new(path, PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET).snaplen(1500).name("eth0")).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        let options = PacketOptions { comments: vec!["first".to_string()], flags: Some(1), drop_count: Some(2), ..Default::default() };
+        pcap_writer.write_packet_with_options(0, vec![1, 2, 3, 4, 5], UNIX_EPOCH + Duration::from_micros(1_500_000), &options).unwrap();
+        pcap_writer.write_custom("this is a test".as_bytes().to_vec()).unwrap();
+        pcap_writer.write_custom(Vec::new()).unwrap();
+        pcap_writer.write_interface_statistics(0, &Statistics { if_recv: Some(10), if_drop: Some(1), ..Default::default() }).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new(path, PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let blocks = pcap_reader.raw_blocks().unwrap().map(|raw| {
+            let mut raw = raw.unwrap();
+            if raw.block_type == crate::PCAPNG_INTERFACE_STATISTICS_BLOCK {
+                raw.data[4..12].fill(0);
+            }
+            raw
+        }).collect();
+        pcap_reader.close();
+        fs::remove_file(path).unwrap();
+        blocks
+    }
+
+    /// Writes a capture with libpcapng's own header through whichever backend is selected and
+    /// reads it back with `read_packets`, returning the file and the blocks read
+    #[cfg(not(feature = "pure-rust"))]
+    fn write_backend_legacy_capture(path: &str) -> (Vec<u8>, Vec<(BlockInfo, Vec<u8>)>) {
+        let mut pcap_writer = PcapNg::new(path, PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet_with_time(vec![1, 2, 3, 4, 5], UNIX_EPOCH + Duration::from_secs(1)).unwrap();
+        pcap_writer.write_custom("this is a test".as_bytes().to_vec()).unwrap();
+        pcap_writer.close();
+
+        let mut blocks = Vec::new();
+        let mut pcap_reader = PcapNg::new(path, PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        pcap_reader.read_packets(|info, bytes| {
+            blocks.push((info, bytes.to_vec()));
+            ControlFlow::<String>::Continue(())
+        }).unwrap();
+        pcap_reader.close();
+        let bytes = fs::read(path).unwrap();
+        fs::remove_file(path).unwrap();
+        (bytes, blocks)
+    }
+
+    #[cfg(not(feature = "pure-rust"))]
+    #[test]
+    fn backends_byte_identical_test() {
+        use crate::test_backend::with_rust_backend;
+        let c_blocks = write_backend_capture("backends_c.pcapng");
+        let rust_blocks = with_rust_backend(|| write_backend_capture("backends_rust.pcapng"));
+        assert_eq!(c_blocks.len(), 6);
+        assert_eq!(c_blocks, rust_blocks);
+
+        let (c_bytes, c_read) = write_backend_legacy_capture("backends_legacy_c.pcapng");
+        let (rust_bytes, rust_read) = with_rust_backend(|| write_backend_legacy_capture("backends_legacy_rust.pcapng"));
+        assert_eq!(c_bytes, rust_bytes);
+        assert_eq!(c_read.len(), 4);
+        assert_eq!(c_read, rust_read);
+    }
+
+    #[cfg(feature = "libpcapng")]
+    #[test]
+    fn backends_truncated_file_test() {
+        use crate::libpcapng_backend;
+        let mut pcap_writer = PcapNg::new("backends_truncated.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        pcap_writer.write_packet(vec![1, 2, 3, 4, 5]).unwrap();
+        pcap_writer.close();
+        let bytes = fs::read("backends_truncated.pcapng").unwrap();
+        fs::write("backends_truncated.pcapng", &bytes[..bytes.len() - 6]).unwrap();
+
+        let mut c_file = libpcapng_backend::File::open("backends_truncated.pcapng".as_ref(), &PcapNgOpenMode::Read).unwrap();
+        let c_result = c_file.read_packets(|_, _| ControlFlow::<String>::Continue(()));
+        c_file.close();
+        let mut pcap_reader = PcapNg::new("backends_truncated.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let rust_result = pcap_reader.raw_blocks().unwrap().last().unwrap();
+        pcap_reader.close();
+        pcap_reader.open().expect("issue opening file");
+        let read_result = pcap_reader.read_packets(|_, _| ControlFlow::<String>::Continue(()));
+        pcap_reader.close();
+        fs::remove_file("backends_truncated.pcapng").unwrap();
+        assert!(matches!(c_result, Err(PcapNgError::MalformedBlock(_))));
+        assert!(matches!(rust_result, Err(PcapNgError::MalformedBlock(_))));
+        assert!(matches!(read_result, Err(PcapNgError::MalformedBlock(_))));
+    }
+
+    #[test]
+    fn corrupt_block_length_test() {
+        let mut pcap_writer = PcapNg::new("corrupt_block_length.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.open().expect("issue opening file");
+        let mut header = Vec::new();
+        header.extend_from_slice(&PCAPNG_ENHANCED_PACKET_BLOCK.to_ne_bytes());
+        header.extend_from_slice(&0xFFFF_FFF0u32.to_ne_bytes());
+        header.extend_from_slice(&[0; 16]);
+        pcap_writer.write_bytes(&header).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("corrupt_block_length.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let last = pcap_reader.raw_blocks().unwrap().last().unwrap();
+        pcap_reader.close();
+        fs::remove_file("corrupt_block_length.pcapng").unwrap();
+        assert!(matches!(last, Err(PcapNgError::MalformedBlock(_))));
+
+        let raw = RawBlock { block_counter: 1, block_type: PCAPNG_ENHANCED_PACKET_BLOCK, block_total_length: 12, data: vec![1, 2] };
+        assert!(raw.body().is_empty());
+    }
+
+    #[test]
+    fn option_with_unexpected_length_test() {
+        let mut bytes = Vec::new();
+        BlockOption::encode_all(&[
+            BlockOption::Unknown { code: 2, value: vec![1, 2] },
+            BlockOption::Comment("still read".to_string()),
+        ], &mut bytes).unwrap();
+        let options = BlockOption::parse_all(PCAPNG_ENHANCED_PACKET_BLOCK, &bytes).unwrap();
+        assert_eq!(options, vec![
+            BlockOption::Unknown { code: 2, value: vec![1, 2] },
+            BlockOption::Comment("still read".to_string()),
+        ]);
+        assert_eq!(BlockOption::parse(PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 9, &[]).unwrap(), BlockOption::Unknown { code: 9, value: Vec::new() });
+    }
+
+    #[test]
+    fn oversized_value_test() {
+        let mut pcap_writer = PcapNg::new("oversized_value.pcapng", PcapNgOpenMode::Write);
+        pcap_writer.add_interface(Interface::new(LINKTYPE_ETHERNET)).unwrap();
+        pcap_writer.open().expect("issue opening file");
+        let long_comment = PacketOptions { comments: vec!["x".repeat(70_000)], ..Default::default() };
+        let result = pcap_writer.write_packet_with_options(0, vec![1, 2, 3], UNIX_EPOCH, &long_comment);
+        assert!(matches!(result, Err(PcapNgError::ValueTooLong(_, 70_000))));
+        let long_name = HashMap::from([(IpAddr::V4(Ipv4Addr::LOCALHOST), vec!["a".repeat(70_000)])]);
+        assert!(matches!(pcap_writer.write_name_resolution(long_name), Err(PcapNgError::ValueTooLong(_, _))));
+        let comment = PacketOptions { comments: vec!["x".repeat(65_535)], ..Default::default() };
+        pcap_writer.write_packet_with_options(0, vec![4, 5, 6], UNIX_EPOCH, &comment).unwrap();
+        pcap_writer.close();
+
+        let mut pcap_reader = PcapNg::new("oversized_value.pcapng", PcapNgOpenMode::Read);
+        pcap_reader.open().expect("issue opening file");
+        let blocks: Vec<Block> = pcap_reader.blocks().unwrap().map(|block| block.unwrap()).collect();
+        pcap_reader.close();
+        fs::remove_file("oversized_value.pcapng").unwrap();
+        assert_eq!(blocks.len(), 3);
+        match &blocks[2] {
+            Block::EnhancedPacket(packet) => {
+                assert_eq!(packet.data, vec![4, 5, 6]);
+                assert_eq!(packet.packet_options().comments, comment.comments);
+            }
+            block => panic!("unexpected block {:?}", block),
+        }
+    }
 }
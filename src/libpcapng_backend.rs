@@ -0,0 +1,151 @@
+use std::any::Any;
+use std::ops::ControlFlow;
+use std::os::raw::{c_char, c_int, c_uchar, c_void};
+use std::os::unix::prelude::OsStrExt;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::path::Path;
+use libc::{fclose, feof, fflush, fopen, fread, free, fwrite, malloc, size_t, FILE};
+use libpcapng_sys::{libpcapng_custom_data_block_size, libpcapng_custom_data_block_write, libpcapng_fp_read, libpcapng_write_header_to_file};
+use crate::{BlockInfo, PcapNgOpenMode, PCAPNG_PEN};
+use crate::PcapNgError::{CallbackError, FileOpenError, FileReadError, FileWriteError, MalformedBlock};
+
+/// A pcap file read and written through libpcapng and the C standard library, flushed and closed
+/// when dropped
+pub(crate) struct File {
+    handle: *mut FILE,
+}
+
+impl File {
+    pub(crate) fn open(path: &Path, mode: &PcapNgOpenMode) -> crate::Result<File> {
+        let mut path_bytes = path.as_os_str().as_bytes().to_vec();
+        path_bytes.push(0);
+        let open_mode = match mode {
+            PcapNgOpenMode::Write => c"wb",
+            PcapNgOpenMode::Append => c"a",
+            PcapNgOpenMode::Read => c"r",
+        };
+        let handle = unsafe { fopen(path_bytes.as_ptr() as *const c_char, open_mode.as_ptr()) };
+        if handle.is_null() {
+            return Err(FileOpenError);
+        }
+        Ok(File { handle })
+    }
+
+    /// Writes all of `bytes` to the file
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> crate::Result<()> {
+        unsafe {
+            if fwrite(bytes.as_ptr() as *const c_void, 1, bytes.len(), self.handle) != bytes.len() {
+                return Err(FileWriteError);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads up to `buf.len()` bytes returning how many were read, a short count means the end
+    /// of the file was reached
+    pub(crate) fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        unsafe {
+            let read = fread(buf.as_mut_ptr() as *mut c_void, 1, buf.len(), self.handle);
+            if read < buf.len() && feof(self.handle) == 0 {
+                return Err(FileReadError);
+            }
+            Ok(read)
+        }
+    }
+
+    /// Writes libpcapng's section header and its single `LINKTYPE_RAW` interface
+    pub(crate) fn write_legacy_header(&mut self) -> crate::Result<()> {
+        unsafe { libpcapng_write_header_to_file(self.handle); }
+        Ok(())
+    }
+
+    /// Writes a custom block owned by libpcapng's `PCAPNG_PEN`
+    pub(crate) fn write_custom(&mut self, data: &[u8]) -> crate::Result<()> {
+        let data_len = data.len() as size_t;
+        unsafe {
+            let buffer_size = libpcapng_custom_data_block_size(data_len);
+            let buffer = malloc(buffer_size) as *mut c_uchar;
+            libpcapng_custom_data_block_write(PCAPNG_PEN, data.as_ptr(), data_len, buffer);
+            let written = fwrite(buffer as *const c_void, buffer_size, 1, self.handle);
+            free(buffer as *mut c_void);
+            if written != 1 {
+                return Err(FileWriteError);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the rest of the file with libpcapng passing every block to the callback, a truncated
+    /// or otherwise unreadable file is returned as [`MalformedBlock`]
+    pub(crate) fn read_packets<F, E>(&mut self, callback_fn: F) -> crate::Result<()>
+    where
+        F: FnMut(BlockInfo, &[u8]) -> ControlFlow<E>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut state: ReadState<F, E> = ReadState {
+            callback_fn,
+            error: None,
+            panic: None,
+        };
+        let result = unsafe {
+            libpcapng_fp_read(self.handle, Some(callback::<F, E>), &mut state as *mut ReadState<F, E> as *mut c_void)
+        };
+        if let Some(panic) = state.panic {
+            resume_unwind(panic);
+        }
+        match state.error {
+            Some(e) => Err(CallbackError(e.into())),
+            None if result != 0 => Err(MalformedBlock(format!("libpcapng failed reading the file ({})", result))),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn close(self) {
+        drop(self);
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        unsafe {
+            fflush(self.handle);
+            fclose(self.handle);
+        }
+    }
+}
+
+/// The state shared with [`callback`] while libpcapng reads the file
+struct ReadState<F, E> {
+    callback_fn: F,
+    error: Option<E>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+unsafe extern "C" fn callback<F, E>(block_counter: u32, block_type: u32, block_total_length: u32, data: *mut c_uchar, userdata: *mut c_void) -> c_int
+where
+    F: FnMut(BlockInfo, &[u8]) -> ControlFlow<E>,
+{
+    let state = &mut *(userdata as *mut ReadState<F, E>);
+    // libpcapng may keep calling after a non zero return, ignore everything once stopped
+    if state.error.is_some() || state.panic.is_some() {
+        return 1;
+    }
+    let info = BlockInfo {
+        block_counter,
+        block_type,
+        block_total_length,
+    };
+    let bytes = std::slice::from_raw_parts(data, (block_total_length as usize).saturating_sub(8));
+    // unwinding across the C frames is undefined, so the panic is carried back to read_packets
+    match catch_unwind(AssertUnwindSafe(|| (state.callback_fn)(info, bytes))) {
+        Ok(ControlFlow::Continue(())) => 0,
+        Ok(ControlFlow::Break(e)) => {
+            state.error = Some(e);
+            1
+        }
+        Err(panic) => {
+            state.panic = Some(panic);
+            1
+        }
+    }
+}
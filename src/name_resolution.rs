@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use crate::{BlockOption, NameRecord, NameResolution};
+
+impl NameResolution {
+    /// Adds a record mapping an address to its names
+    pub fn push(&mut self, address: IpAddr, names: Vec<String>) {
+        self.records.push(match address {
+            IpAddr::V4(address) => NameRecord::Ipv4 { address, names },
+            IpAddr::V6(address) => NameRecord::Ipv6 { address, names },
+        });
+    }
+
+    /// The names of each address in the block, records for the same address are merged
+    pub fn to_map(&self) -> HashMap<IpAddr, Vec<String>> {
+        let mut map = HashMap::new();
+        self.merge_into(&mut map);
+        map
+    }
+
+    /// Adds the names of each address in the block to `map`, skipping names already present
+    pub(crate) fn merge_into(&self, map: &mut HashMap<IpAddr, Vec<String>>) {
+        for record in &self.records {
+            let (address, names) = match record {
+                NameRecord::Ipv4 { address, names } => (IpAddr::V4(*address), names),
+                NameRecord::Ipv6 { address, names } => (IpAddr::V6(*address), names),
+                NameRecord::Unknown { .. } => continue,
+            };
+            let known: &mut Vec<String> = map.entry(address).or_default();
+            for name in names {
+                if !known.contains(name) {
+                    known.push(name.clone());
+                }
+            }
+        }
+    }
+
+    /// The `ns_dnsname` option, the name of the DNS server used for name resolution
+    pub fn dns_name(&self) -> Option<&str> {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::NsDnsName(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The `ns_dnsIP4addr` option, the IPv4 address of the DNS server
+    pub fn dns_ipv4_address(&self) -> Option<Ipv4Addr> {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::NsDnsIp4Addr(address) => Some(*address),
+            _ => None,
+        })
+    }
+
+    /// The `ns_dnsIP6addr` option, the IPv6 address of the DNS server
+    pub fn dns_ipv6_address(&self) -> Option<Ipv6Addr> {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::NsDnsIp6Addr(address) => Some(*address),
+            _ => None,
+        })
+    }
+}
+
+/// Builds a block with one record per address, ordered by address so the output is stable
+impl From<HashMap<IpAddr, Vec<String>>> for NameResolution {
+    fn from(map: HashMap<IpAddr, Vec<String>>) -> Self {
+        let mut entries: Vec<(IpAddr, Vec<String>)> = map.into_iter().collect();
+        entries.sort();
+        let mut name_resolution = NameResolution {
+            records: Vec::new(),
+            options: Vec::new(),
+        };
+        for (address, names) in entries {
+            name_resolution.push(address, names);
+        }
+        name_resolution
+    }
+}
@@ -0,0 +1,320 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use crate::{PCAPNG_ENHANCED_PACKET_BLOCK, PCAPNG_INTERFACE_DESCRIPTION_BLOCK, PCAPNG_INTERFACE_STATISTICS_BLOCK, PCAPNG_NAME_RESOLUTION_BLOCK, PCAPNG_PACKET_BLOCK, PCAPNG_SECTION_HEADER_BLOCK};
+use crate::block::{put_padded, BodyReader};
+use crate::PcapNgError::{MalformedBlock, ValueTooLong};
+use crate::TsResolution;
+
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const OPT_CUSTOM_UTF8: u16 = 2988;
+const OPT_CUSTOM_BINARY: u16 = 2989;
+const OPT_CUSTOM_UTF8_NOCOPY: u16 = 19372;
+const OPT_CUSTOM_BINARY_NOCOPY: u16 = 19373;
+
+/// An option attached to a block
+///
+/// Option codes other than the comment and custom options mean different things depending on
+/// the block they are attached to, so the variants are named after the block type they belong to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BlockOption {
+    /// `opt_comment`, a comment about the block
+    Comment(String),
+    /// `opt_custom`, application data tagged with a Private Enterprise Number
+    Custom {
+        /// Whether the option may be copied to new files by tools which do not understand it
+        copyable: bool,
+        /// Whether the data is a UTF-8 string rather than binary
+        utf8: bool,
+        /// The Private Enterprise Number identifying the owner of the data
+        pen: u32,
+        /// The custom data
+        data: Vec<u8>,
+    },
+    /// `shb_hardware`, the hardware the section was created on
+    ShbHardware(String),
+    /// `shb_os`, the operating system the section was created on
+    ShbOs(String),
+    /// `shb_userappl`, the application which created the section
+    ShbUserAppl(String),
+    /// `if_name`, the name of the interface
+    IfName(String),
+    /// `if_description`, a description of the interface
+    IfDescription(String),
+    /// `if_IPv4addr`, an address and netmask of the interface
+    IfIpv4Addr(Ipv4Addr, Ipv4Addr),
+    /// `if_IPv6addr`, an address and prefix length of the interface
+    IfIpv6Addr(Ipv6Addr, u8),
+    /// `if_MACaddr`, the MAC address of the interface
+    IfMacAddr([u8; 6]),
+    /// `if_EUIaddr`, the EUI-64 address of the interface
+    IfEuiAddr([u8; 8]),
+    /// `if_speed`, the interface speed in bits per second
+    IfSpeed(u64),
+    /// `if_tsresol`, the resolution of the interface's timestamps
+    IfTsResol(TsResolution),
+    /// `if_tzone`, the time zone of the interface's timestamps
+    IfTzone(u32),
+    /// `if_filter`, the capture filter used on the interface
+    IfFilter {
+        /// 0 for a libpcap filter string, 1 for BPF bytecode
+        filter_type: u8,
+        /// The filter
+        filter: Vec<u8>,
+    },
+    /// `if_os`, the operating system of the machine the interface is on
+    IfOs(String),
+    /// `if_fcslen`, the length of the frame check sequence of the interface
+    IfFcsLen(u8),
+    /// `if_tsoffset`, seconds to add to the interface's timestamps
+    IfTsOffset(i64),
+    /// `if_hardware`, a description of the interface hardware
+    IfHardware(String),
+    /// `if_txspeed`, the transmit speed in bits per second
+    IfTxSpeed(u64),
+    /// `if_rxspeed`, the receive speed in bits per second
+    IfRxSpeed(u64),
+    /// `if_iana_tzname`, the IANA time zone name of the interface's timestamps
+    IfIanaTzName(String),
+    /// `epb_flags`, direction, reception type and link layer error flags of a packet
+    EpbFlags(u32),
+    /// `epb_hash`, a hash of the packet
+    EpbHash {
+        /// The hash algorithm, 2 for CRC32, 3 for MD5, 4 for SHA-1 and so on
+        algorithm: u8,
+        /// The hash value
+        hash: Vec<u8>,
+    },
+    /// `epb_dropcount`, packets lost between this packet and the previous one
+    EpbDropCount(u64),
+    /// `epb_packetid`, an id identifying the packet across captures
+    EpbPacketId(u64),
+    /// `epb_queue`, the queue the packet was received on
+    EpbQueue(u32),
+    /// `epb_verdict`, the verdict of a filter on the packet
+    EpbVerdict {
+        /// 0 for hardware, 1 for Linux eBPF TC and 2 for Linux eBPF XDP verdicts
+        verdict_type: u8,
+        /// The verdict
+        verdict: Vec<u8>,
+    },
+    /// `ns_dnsname`, the name of the DNS server used for name resolution
+    NsDnsName(String),
+    /// `ns_dnsIP4addr`, the IPv4 address of the DNS server
+    NsDnsIp4Addr(Ipv4Addr),
+    /// `ns_dnsIP6addr`, the IPv6 address of the DNS server
+    NsDnsIp6Addr(Ipv6Addr),
+    /// `isb_starttime`, when the statistics started being collected
+    IsbStartTime(u64),
+    /// `isb_endtime`, when the statistics were last updated
+    IsbEndTime(u64),
+    /// `isb_ifrecv`, packets received by the interface
+    IsbIfRecv(u64),
+    /// `isb_ifdrop`, packets dropped by the interface
+    IsbIfDrop(u64),
+    /// `isb_filteraccept`, packets accepted by the capture filter
+    IsbFilterAccept(u64),
+    /// `isb_osdrop`, packets dropped by the operating system
+    IsbOsDrop(u64),
+    /// `isb_usrdeliv`, packets delivered to the user
+    IsbUsrDeliv(u64),
+    /// An option this crate does not know about
+    Unknown {
+        /// The option code
+        code: u16,
+        /// The option value without padding
+        value: Vec<u8>,
+    },
+}
+
+impl BlockOption {
+    /// Parses the options at the end of a block body, stopping at `opt_endofopt` or the end of the bytes
+    pub fn parse_all(block_type: u32, bytes: &[u8]) -> crate::Result<Vec<BlockOption>> {
+        let mut reader = BodyReader::new(bytes);
+        let mut options = Vec::new();
+        while reader.remaining() >= 4 {
+            let code = reader.u16()?;
+            let length = reader.u16()?;
+            if code == OPT_ENDOFOPT {
+                break;
+            }
+            let value = reader.padded_bytes(length as usize)?;
+            options.push(BlockOption::parse(block_type, code, value)?);
+        }
+        Ok(options)
+    }
+
+    /// Parses a single option from its code and unpadded value
+    ///
+    /// A known option whose value has an unexpected length is returned as [`BlockOption::Unknown`]
+    /// so one odd option written by another tool does not make the rest of the capture unreadable.
+    pub fn parse(block_type: u32, code: u16, value: &[u8]) -> crate::Result<BlockOption> {
+        Ok(Self::decode(block_type, code, value).unwrap_or_else(|_| BlockOption::Unknown {
+            code,
+            value: value.to_vec(),
+        }))
+    }
+
+    fn decode(block_type: u32, code: u16, value: &[u8]) -> crate::Result<BlockOption> {
+        let option = match (block_type, code) {
+            (_, OPT_COMMENT) => BlockOption::Comment(string(value)),
+            (_, OPT_CUSTOM_UTF8 | OPT_CUSTOM_BINARY | OPT_CUSTOM_UTF8_NOCOPY | OPT_CUSTOM_BINARY_NOCOPY) => BlockOption::Custom {
+                copyable: code == OPT_CUSTOM_UTF8 || code == OPT_CUSTOM_BINARY,
+                utf8: code == OPT_CUSTOM_UTF8 || code == OPT_CUSTOM_UTF8_NOCOPY,
+                pen: u32::from_ne_bytes(fixed(code, value.get(..4).unwrap_or(value))?),
+                data: value[4..].to_vec(),
+            },
+            (PCAPNG_SECTION_HEADER_BLOCK, 2) => BlockOption::ShbHardware(string(value)),
+            (PCAPNG_SECTION_HEADER_BLOCK, 3) => BlockOption::ShbOs(string(value)),
+            (PCAPNG_SECTION_HEADER_BLOCK, 4) => BlockOption::ShbUserAppl(string(value)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 2) => BlockOption::IfName(string(value)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 3) => BlockOption::IfDescription(string(value)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 4) => {
+                let value: [u8; 8] = fixed(code, value)?;
+                BlockOption::IfIpv4Addr(Ipv4Addr::from(<[u8; 4]>::try_from(&value[..4]).unwrap()), Ipv4Addr::from(<[u8; 4]>::try_from(&value[4..]).unwrap()))
+            }
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 5) => {
+                let value: [u8; 17] = fixed(code, value)?;
+                BlockOption::IfIpv6Addr(Ipv6Addr::from(<[u8; 16]>::try_from(&value[..16]).unwrap()), value[16])
+            }
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 6) => BlockOption::IfMacAddr(fixed(code, value)?),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 7) => BlockOption::IfEuiAddr(fixed(code, value)?),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 8) => BlockOption::IfSpeed(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 9) => BlockOption::IfTsResol(TsResolution::from_byte(fixed::<1>(code, value)?[0])),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 10) => BlockOption::IfTzone(u32::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 11) if !value.is_empty() => BlockOption::IfFilter {
+                filter_type: value[0],
+                filter: value[1..].to_vec(),
+            },
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 12) => BlockOption::IfOs(string(value)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 13) => BlockOption::IfFcsLen(fixed::<1>(code, value)?[0]),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 14) => BlockOption::IfTsOffset(i64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 15) => BlockOption::IfHardware(string(value)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 16) => BlockOption::IfTxSpeed(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 17) => BlockOption::IfRxSpeed(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_DESCRIPTION_BLOCK, 18) => BlockOption::IfIanaTzName(string(value)),
+            (PCAPNG_ENHANCED_PACKET_BLOCK | PCAPNG_PACKET_BLOCK, 2) => BlockOption::EpbFlags(u32::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_ENHANCED_PACKET_BLOCK | PCAPNG_PACKET_BLOCK, 3) if !value.is_empty() => BlockOption::EpbHash {
+                algorithm: value[0],
+                hash: value[1..].to_vec(),
+            },
+            (PCAPNG_ENHANCED_PACKET_BLOCK, 4) => BlockOption::EpbDropCount(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_ENHANCED_PACKET_BLOCK, 5) => BlockOption::EpbPacketId(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_ENHANCED_PACKET_BLOCK, 6) => BlockOption::EpbQueue(u32::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_ENHANCED_PACKET_BLOCK, 7) if !value.is_empty() => BlockOption::EpbVerdict {
+                verdict_type: value[0],
+                verdict: value[1..].to_vec(),
+            },
+            (PCAPNG_NAME_RESOLUTION_BLOCK, 2) => BlockOption::NsDnsName(string(value)),
+            (PCAPNG_NAME_RESOLUTION_BLOCK, 3) => BlockOption::NsDnsIp4Addr(Ipv4Addr::from(fixed::<4>(code, value)?)),
+            (PCAPNG_NAME_RESOLUTION_BLOCK, 4) => BlockOption::NsDnsIp6Addr(Ipv6Addr::from(fixed::<16>(code, value)?)),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 2) => BlockOption::IsbStartTime(timestamp(code, value)?),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 3) => BlockOption::IsbEndTime(timestamp(code, value)?),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 4) => BlockOption::IsbIfRecv(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 5) => BlockOption::IsbIfDrop(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 6) => BlockOption::IsbFilterAccept(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 7) => BlockOption::IsbOsDrop(u64::from_ne_bytes(fixed(code, value)?)),
+            (PCAPNG_INTERFACE_STATISTICS_BLOCK, 8) => BlockOption::IsbUsrDeliv(u64::from_ne_bytes(fixed(code, value)?)),
+            _ => BlockOption::Unknown {
+                code,
+                value: value.to_vec(),
+            },
+        };
+        Ok(option)
+    }
+
+    /// The option code written for this option
+    pub fn code(&self) -> u16 {
+        match self {
+            BlockOption::Comment(_) => OPT_COMMENT,
+            BlockOption::Custom { copyable, utf8, .. } => match (copyable, utf8) {
+                (true, true) => OPT_CUSTOM_UTF8,
+                (true, false) => OPT_CUSTOM_BINARY,
+                (false, true) => OPT_CUSTOM_UTF8_NOCOPY,
+                (false, false) => OPT_CUSTOM_BINARY_NOCOPY,
+            },
+            BlockOption::ShbHardware(_) | BlockOption::IfName(_) | BlockOption::EpbFlags(_) | BlockOption::NsDnsName(_) | BlockOption::IsbStartTime(_) => 2,
+            BlockOption::ShbOs(_) | BlockOption::IfDescription(_) | BlockOption::EpbHash { .. } | BlockOption::NsDnsIp4Addr(_) | BlockOption::IsbEndTime(_) => 3,
+            BlockOption::ShbUserAppl(_) | BlockOption::IfIpv4Addr(..) | BlockOption::EpbDropCount(_) | BlockOption::NsDnsIp6Addr(_) | BlockOption::IsbIfRecv(_) => 4,
+            BlockOption::IfIpv6Addr(..) | BlockOption::EpbPacketId(_) | BlockOption::IsbIfDrop(_) => 5,
+            BlockOption::IfMacAddr(_) | BlockOption::EpbQueue(_) | BlockOption::IsbFilterAccept(_) => 6,
+            BlockOption::IfEuiAddr(_) | BlockOption::EpbVerdict { .. } | BlockOption::IsbOsDrop(_) => 7,
+            BlockOption::IfSpeed(_) | BlockOption::IsbUsrDeliv(_) => 8,
+            BlockOption::IfTsResol(_) => 9,
+            BlockOption::IfTzone(_) => 10,
+            BlockOption::IfFilter { .. } => 11,
+            BlockOption::IfOs(_) => 12,
+            BlockOption::IfFcsLen(_) => 13,
+            BlockOption::IfTsOffset(_) => 14,
+            BlockOption::IfHardware(_) => 15,
+            BlockOption::IfTxSpeed(_) => 16,
+            BlockOption::IfRxSpeed(_) => 17,
+            BlockOption::IfIanaTzName(_) => 18,
+            BlockOption::Unknown { code, .. } => *code,
+        }
+    }
+
+    /// The option value without padding
+    pub fn value(&self) -> Vec<u8> {
+        match self {
+            BlockOption::Comment(s) | BlockOption::ShbHardware(s) | BlockOption::ShbOs(s) | BlockOption::ShbUserAppl(s)
+            | BlockOption::IfName(s) | BlockOption::IfDescription(s) | BlockOption::IfOs(s) | BlockOption::IfHardware(s)
+            | BlockOption::IfIanaTzName(s) | BlockOption::NsDnsName(s) => s.as_bytes().to_vec(),
+            BlockOption::Custom { pen, data, .. } => [&pen.to_ne_bytes()[..], data].concat(),
+            BlockOption::IfIpv4Addr(address, netmask) => [address.octets(), netmask.octets()].concat(),
+            BlockOption::IfIpv6Addr(address, prefix_length) => [&address.octets()[..], &[*prefix_length]].concat(),
+            BlockOption::IfMacAddr(address) => address.to_vec(),
+            BlockOption::IfEuiAddr(address) => address.to_vec(),
+            BlockOption::IfTsResol(resolution) => vec![resolution.to_byte()],
+            BlockOption::IfTzone(v) | BlockOption::EpbFlags(v) | BlockOption::EpbQueue(v) => v.to_ne_bytes().to_vec(),
+            BlockOption::IfFilter { filter_type: kind, filter: data } | BlockOption::EpbHash { algorithm: kind, hash: data }
+            | BlockOption::EpbVerdict { verdict_type: kind, verdict: data } => [&[*kind], &data[..]].concat(),
+            BlockOption::IfFcsLen(length) => vec![*length],
+            BlockOption::IfTsOffset(offset) => offset.to_ne_bytes().to_vec(),
+            BlockOption::IfSpeed(v) | BlockOption::IfTxSpeed(v) | BlockOption::IfRxSpeed(v) | BlockOption::EpbDropCount(v)
+            | BlockOption::EpbPacketId(v) | BlockOption::IsbIfRecv(v) | BlockOption::IsbIfDrop(v) | BlockOption::IsbFilterAccept(v)
+            | BlockOption::IsbOsDrop(v) | BlockOption::IsbUsrDeliv(v) => v.to_ne_bytes().to_vec(),
+            BlockOption::IsbStartTime(v) | BlockOption::IsbEndTime(v) => [((v >> 32) as u32).to_ne_bytes(), (*v as u32).to_ne_bytes()].concat(),
+            BlockOption::NsDnsIp4Addr(address) => address.octets().to_vec(),
+            BlockOption::NsDnsIp6Addr(address) => address.octets().to_vec(),
+            BlockOption::Unknown { value, .. } => value.clone(),
+        }
+    }
+
+    /// Serializes the options followed by `opt_endofopt`, writing nothing when there are no options
+    ///
+    /// Values longer than the 65535 bytes an option length can hold are returned as
+    /// [`PcapNgError::ValueTooLong`](crate::PcapNgError::ValueTooLong).
+    pub fn encode_all(options: &[BlockOption], out: &mut Vec<u8>) -> crate::Result<()> {
+        if options.is_empty() {
+            return Ok(());
+        }
+        for option in options {
+            let value = option.value();
+            let length = u16::try_from(value.len()).map_err(|_| ValueTooLong(format!("option {}", option.code()), value.len()))?;
+            out.extend_from_slice(&option.code().to_ne_bytes());
+            out.extend_from_slice(&length.to_ne_bytes());
+            put_padded(out, &value);
+        }
+        out.extend_from_slice(&OPT_ENDOFOPT.to_ne_bytes());
+        out.extend_from_slice(&0u16.to_ne_bytes());
+        Ok(())
+    }
+}
+
+/// Decodes a string option, dropping the zero terminator some writers add
+fn string(value: &[u8]) -> String {
+    String::from_utf8_lossy(value).trim_end_matches('\0').to_string()
+}
+
+/// Checks a fixed length option value has the expected length
+fn fixed<const N: usize>(code: u16, value: &[u8]) -> crate::Result<[u8; N]> {
+    value.try_into().map_err(|_| MalformedBlock(format!("option {} has {} bytes, expected {}", code, value.len(), N)))
+}
+
+/// Decodes a timestamp stored as its upper then lower 32 bits
+fn timestamp(code: u16, value: &[u8]) -> crate::Result<u64> {
+    let value: [u8; 8] = fixed(code, value)?;
+    let high = u32::from_ne_bytes(value[..4].try_into().unwrap()) as u64;
+    let low = u32::from_ne_bytes(value[4..].try_into().unwrap()) as u64;
+    Ok(high << 32 | low)
+}
@@ -0,0 +1,132 @@
+use crate::{BlockOption, EnhancedPacket};
+
+/// The options of an enhanced packet block, written with [`PcapNg::write_packet_with_options`](crate::PcapNg::write_packet_with_options)
+/// and read with [`EnhancedPacket::packet_options`]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PacketOptions {
+    /// `opt_comment`, comments about the packet
+    pub comments: Vec<String>,
+    /// `epb_flags`, direction, reception type and link layer error flags
+    pub flags: Option<u32>,
+    /// `epb_hash`, a hash of the packet
+    pub hash: Option<PacketHash>,
+    /// `epb_dropcount`, packets lost between this packet and the previous one
+    pub drop_count: Option<u64>,
+    /// `epb_packetid`, an id identifying the packet across captures
+    pub packet_id: Option<u64>,
+    /// `epb_queue`, the queue the packet was received on
+    pub queue: Option<u32>,
+    /// `epb_verdict`, the verdict of a filter on the packet
+    pub verdict: Option<PacketVerdict>,
+}
+
+/// The value of an `epb_hash` option
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PacketHash {
+    /// The hash algorithm, 2 for CRC32, 3 for MD5, 4 for SHA-1 and so on
+    pub algorithm: u8,
+    /// The hash value
+    pub hash: Vec<u8>,
+}
+
+/// The value of an `epb_verdict` option
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PacketVerdict {
+    /// 0 for hardware, 1 for Linux eBPF TC and 2 for Linux eBPF XDP verdicts
+    pub verdict_type: u8,
+    /// The verdict
+    pub verdict: Vec<u8>,
+}
+
+/// The direction of a packet, stored in the two lowest bits of `epb_flags`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PacketDirection {
+    /// No direction information
+    Unknown,
+    /// The packet was received
+    Inbound,
+    /// The packet was sent
+    Outbound,
+}
+
+impl PacketOptions {
+    /// The direction of the packet from its flags
+    pub fn direction(&self) -> PacketDirection {
+        match self.flags.unwrap_or(0) & 0b11 {
+            1 => PacketDirection::Inbound,
+            2 => PacketDirection::Outbound,
+            _ => PacketDirection::Unknown,
+        }
+    }
+
+    /// Sets the direction bits of the flags, leaving the other flags untouched
+    pub fn set_direction(&mut self, direction: PacketDirection) {
+        let bits = match direction {
+            PacketDirection::Unknown => 0,
+            PacketDirection::Inbound => 1,
+            PacketDirection::Outbound => 2,
+        };
+        self.flags = Some(self.flags.unwrap_or(0) & !0b11 | bits);
+    }
+
+    /// Collects the packet options out of a block's options, ignoring any others
+    pub fn from_options(options: &[BlockOption]) -> Self {
+        let mut packet_options = PacketOptions::default();
+        for option in options {
+            match option {
+                BlockOption::Comment(comment) => packet_options.comments.push(comment.clone()),
+                BlockOption::EpbFlags(flags) => packet_options.flags = Some(*flags),
+                BlockOption::EpbHash { algorithm, hash } => packet_options.hash = Some(PacketHash {
+                    algorithm: *algorithm,
+                    hash: hash.clone(),
+                }),
+                BlockOption::EpbDropCount(drop_count) => packet_options.drop_count = Some(*drop_count),
+                BlockOption::EpbPacketId(packet_id) => packet_options.packet_id = Some(*packet_id),
+                BlockOption::EpbQueue(queue) => packet_options.queue = Some(*queue),
+                BlockOption::EpbVerdict { verdict_type, verdict } => packet_options.verdict = Some(PacketVerdict {
+                    verdict_type: *verdict_type,
+                    verdict: verdict.clone(),
+                }),
+                _ => {}
+            }
+        }
+        packet_options
+    }
+
+    /// Converts the packet options into block options
+    pub fn to_options(&self) -> Vec<BlockOption> {
+        let mut options: Vec<BlockOption> = self.comments.iter().cloned().map(BlockOption::Comment).collect();
+        if let Some(flags) = self.flags {
+            options.push(BlockOption::EpbFlags(flags));
+        }
+        if let Some(hash) = &self.hash {
+            options.push(BlockOption::EpbHash {
+                algorithm: hash.algorithm,
+                hash: hash.hash.clone(),
+            });
+        }
+        if let Some(drop_count) = self.drop_count {
+            options.push(BlockOption::EpbDropCount(drop_count));
+        }
+        if let Some(packet_id) = self.packet_id {
+            options.push(BlockOption::EpbPacketId(packet_id));
+        }
+        if let Some(queue) = self.queue {
+            options.push(BlockOption::EpbQueue(queue));
+        }
+        if let Some(verdict) = &self.verdict {
+            options.push(BlockOption::EpbVerdict {
+                verdict_type: verdict.verdict_type,
+                verdict: verdict.verdict.clone(),
+            });
+        }
+        options
+    }
+}
+
+impl EnhancedPacket {
+    /// The packet's comment, flags, hash, drop count, packet id, queue and verdict options
+    pub fn packet_options(&self) -> PacketOptions {
+        PacketOptions::from_options(&self.options)
+    }
+}
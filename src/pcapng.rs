@@ -1,26 +1,22 @@
-use std::intrinsics::transmute;
-use std::os::raw::{c_int, c_uchar, c_void};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::ops::ControlFlow;
 use std::path::PathBuf;
-use std::ptr::null_mut;
-use std::os::unix::prelude::OsStrExt;
-#[cfg(not(feature = "debian"))]
-use libc::c_char;
-use libc::{fclose, fflush, FILE, fopen, fwrite, malloc, size_t};
-use libpcapng_sys::{libpcapng_custom_data_block_size, libpcapng_custom_data_block_write, libpcapng_fp_read, libpcapng_write_enhanced_packet_to_file, libpcapng_write_enhanced_packet_with_time_to_file, libpcapng_write_header_to_file, PCAPNG_PEN};
-use crate::PcapNgError::{FileOpenError, FileNotOpen, OperationOnlySupportedInReadMode, OperationOnlySupportedInWriteMode};
-
-/// Type for casting callback function a mutable void pointer
-pub type VoidPtr = *mut c_void;
-
-/// Type for casting a void pointer to the callback function signature
-pub type CbFn = fn(u32, u32, u32, Vec<u8>);
+use std::time::SystemTime;
+use crate::backend;
+use crate::{Block, BlockInfo, Blocks, CustomBlock, CustomBlockCodec, CustomBlockRegistry, CustomValue, DecryptionSecrets, EnhancedPacket, Interface, InterfaceDescription, InterfaceStatistics, NameResolution, PacketOptions, RawBlocks, SecretsKind, Section, SectionHeader, SimplePacket, Statistics, Timestamp, LINKTYPE_RAW, PCAPNG_INTERFACE_DESCRIPTION_BLOCK, PCAPNG_SECTION_HEADER_BLOCK};
+use crate::PcapNgError::{FileNotOpen, OperationOnlySupportedInReadMode, OperationOnlySupportedInWriteMode, OriginalLengthTooShort, SectionHeaderAlreadyWritten, UnknownInterface};
 
 
 /// A struct which provides an interface to interact with the libpcapng functions in a cohesive way
 pub struct PcapNg {
     file_path: PathBuf,
-    file_handle: Option<*mut FILE>,
+    file: Option<backend::File>,
     mode: PcapNgOpenMode,
+    pub(crate) block_counter: u32,
+    pub(crate) interfaces: Vec<InterfaceDescription>,
+    truncate_to_snaplen: bool,
+    section_header: Option<SectionHeader>,
 }
 
 impl PcapNg {
@@ -29,131 +25,415 @@ impl PcapNg {
     pub fn new<P: Into<PathBuf>>(path: P, mode: PcapNgOpenMode) -> Self {
         PcapNg {
             file_path: path.into(),
-            file_handle: None,
+            file: None,
             mode,
+            block_counter: 0,
+            interfaces: Vec::new(),
+            truncate_to_snaplen: false,
+            section_header: None,
         }
     }
 
     /// Opens the pcap file
     pub fn open(&mut self) -> crate::Result<()> {
-        unsafe {
-            let mut path_bytes = self.file_path.as_os_str().as_bytes().to_vec();
-            path_bytes.push(0);
-            let fh = match self.mode {
-                #[cfg(feature="debian")]
-                PcapNgOpenMode::Write => fopen(path_bytes.as_ptr(), "wb\0".as_ptr()),
-                #[cfg(not(feature="debian"))]
-                PcapNgOpenMode::Write => fopen(path_bytes.as_ptr() as *const i8, "wb\0".as_ptr() as *const c_char),
-                #[cfg(feature="debian")]
-                PcapNgOpenMode::Append => fopen(path_bytes.as_ptr(), "a\0".as_ptr()),
-                #[cfg(not(feature="debian"))]
-                PcapNgOpenMode::Append => fopen(path_bytes.as_ptr() as *const i8, "a\0".as_ptr() as *const c_char),
-                #[cfg(feature="debian")]
-                PcapNgOpenMode::Read => fopen(path_bytes.as_ptr(), "r\0".as_ptr()),
-                #[cfg(not(feature="debian"))]
-                PcapNgOpenMode::Read => fopen(path_bytes.as_ptr() as *const i8, "r\0".as_ptr() as *const c_char),
-            };
-
-            if fh.is_null() {
-                Err(FileOpenError)
-            } else {
-                if self.mode == PcapNgOpenMode::Write { libpcapng_write_header_to_file(fh); }
-                self.file_handle = Some(fh);
+        match self.mode {
+            PcapNgOpenMode::Append => self.interfaces = self.existing_interfaces()?,
+            PcapNgOpenMode::Read => self.interfaces.clear(),
+            PcapNgOpenMode::Write => {}
+        }
+        self.open_file()?;
+        if self.mode == PcapNgOpenMode::Write {
+            self.write_header()?;
+        }
+        Ok(())
+    }
+
+    /// Opens the file handle without writing anything, used when copying blocks from another file
+    pub(crate) fn open_file(&mut self) -> crate::Result<()> {
+        self.file = Some(backend::File::open(&self.file_path, &self.mode)?);
+        self.block_counter = 0;
+        Ok(())
+    }
+
+    /// Writes the section header followed by the interfaces added before opening, falling back to
+    /// libpcapng's header with its single `LINKTYPE_RAW` interface when none were added
+    fn write_header(&mut self) -> crate::Result<()> {
+        if self.interfaces.is_empty() && self.section_header.is_none() {
+            match self.file.as_mut() {
+                Some(file) => file.write_legacy_header()?,
+                None => return Err(FileNotOpen),
+            }
+            self.interfaces.push(Interface::new(LINKTYPE_RAW).into());
+            return Ok(());
+        }
+        if self.interfaces.is_empty() {
+            self.interfaces.push(Interface::new(LINKTYPE_RAW).into());
+        }
+        let section_header = self.section_header.clone().unwrap_or_else(|| Section::new().into());
+        let mut bytes = Block::SectionHeader(section_header).encode()?;
+        for interface in &self.interfaces {
+            bytes.extend_from_slice(&Block::InterfaceDescription(interface.clone()).encode()?);
+        }
+        self.write_bytes(&bytes)
+    }
+
+    /// Sets the section header written when the file is opened in write mode, use [`Section`] to
+    /// add `shb_hardware`, `shb_os`, `shb_userappl` and comments
+    pub fn set_section_header<S: Into<SectionHeader>>(&mut self, section_header: S) -> crate::Result<()> {
+        match self.mode {
+            PcapNgOpenMode::Read => Err(OperationOnlySupportedInWriteMode),
+            PcapNgOpenMode::Append => Err(SectionHeaderAlreadyWritten),
+            PcapNgOpenMode::Write if self.file.is_some() => Err(SectionHeaderAlreadyWritten),
+            PcapNgOpenMode::Write => {
+                self.section_header = Some(section_header.into());
                 Ok(())
             }
         }
     }
 
-    /// Write a custom frame to the pcap
+    /// Reads the interfaces of the last section of an existing file so appended blocks can refer to them
+    fn existing_interfaces(&self) -> crate::Result<Vec<InterfaceDescription>> {
+        let mut reader = PcapNg::new(self.file_path.clone(), PcapNgOpenMode::Read);
+        if reader.open().is_err() {
+            return Ok(Vec::new());
+        }
+        let mut interfaces = Vec::new();
+        for raw in reader.raw_blocks()? {
+            let raw = raw?;
+            match raw.block_type {
+                PCAPNG_SECTION_HEADER_BLOCK => interfaces.clear(),
+                PCAPNG_INTERFACE_DESCRIPTION_BLOCK => {
+                    if let Block::InterfaceDescription(interface) = Block::decode(raw)? {
+                        interfaces.push(interface);
+                    }
+                }
+                _ => {}
+            }
+        }
+        reader.close();
+        Ok(interfaces)
+    }
+
+    /// Declares an interface packets can be written against and returns its interface id
+    ///
+    /// Interfaces added before a file is opened in write mode are written right after the section
+    /// header in place of libpcapng's default `LINKTYPE_RAW` interface, the first one getting id 0.
+    /// Interfaces added to an open file are written immediately.
+    pub fn add_interface<I: Into<InterfaceDescription>>(&mut self, interface: I) -> crate::Result<u32> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
+        }
+        let interface = interface.into();
+        if self.file.is_some() {
+            self.write_bytes(&Block::InterfaceDescription(interface.clone()).encode()?)?;
+        } else if self.mode == PcapNgOpenMode::Append {
+            // the ids of an existing file's interfaces are only known once it has been opened
+            return Err(FileNotOpen);
+        }
+        self.interfaces.push(interface);
+        Ok(self.interfaces.len() as u32 - 1)
+    }
+
+    /// The interfaces of the current section in id order, when reading these are the interfaces
+    /// seen so far by [`PcapNg::blocks`]
+    pub fn interfaces(&self) -> &[InterfaceDescription] {
+        &self.interfaces
+    }
+
+    /// Write a custom frame to the pcap, owned by libpcapng's `PCAPNG_PEN`, see [`PcapNg::write_custom_with_pen`]
+    /// to use your own Private Enterprise Number
     pub fn write_custom(&mut self, data: Vec<u8>) -> crate::Result<()> {
         if self.mode == PcapNgOpenMode::Read {
             return Err(OperationOnlySupportedInWriteMode);
         }
-        let data_len = data.len() as size_t;
-        unsafe {
-            let buffer_size = libpcapng_custom_data_block_size(data_len);
-            let buffer = malloc(buffer_size) as *mut c_uchar;
-            let data_bytes = data.as_ptr();
-            libpcapng_custom_data_block_write(PCAPNG_PEN, data_bytes, data_len, buffer);
-            if let Some(fh) = self.file_handle {
-                fwrite(buffer as *const c_void, buffer_size, 1, fh);
-                Ok(())
-            } else {
-                Err(FileNotOpen)
-            }
+        match self.file.as_mut() {
+            Some(file) => file.write_custom(&data),
+            None => Err(FileNotOpen),
         }
     }
 
-    /// Writes a packet frame to the pcap
-    pub fn write_packet(&mut self, data: Vec<u8>) -> crate::Result<()> {
+    /// Writes a custom block owned by the Private Enterprise Number `pen`
+    ///
+    /// `copyable` selects between `PCAPNG_CUSTOM_DATA_BLOCK`, which tools may copy to new files,
+    /// and `PCAPNG_CUSTOM_DATA_BLOCK_NOCOPY`. The data is padded to 32 bits.
+    pub fn write_custom_with_pen(&mut self, pen: u32, copyable: bool, data: Vec<u8>) -> crate::Result<()> {
         if self.mode == PcapNgOpenMode::Read {
             return Err(OperationOnlySupportedInWriteMode);
         }
-        let data_len = data.len() as size_t;
-        let data_bytes = data.as_ptr() as *mut c_uchar;
-        unsafe {
-            if let Some(fh) = self.file_handle {
-                libpcapng_write_enhanced_packet_to_file(fh, data_bytes, data_len);
-                Ok(())
-            } else {
-                Err(FileNotOpen)
+        self.write_bytes(&Block::Custom(CustomBlock { copyable, pen, data }).encode()?)
+    }
+
+    /// Reads the rest of the pcap and returns every custom block, with the Private Enterprise
+    /// Number separate from the data
+    pub fn read_custom(&mut self) -> crate::Result<Vec<CustomBlock>> {
+        let mut custom_blocks = Vec::new();
+        for block in self.blocks()? {
+            if let Block::Custom(custom) = block? {
+                custom_blocks.push(custom);
             }
         }
+        Ok(custom_blocks)
     }
 
-    /// Reads all the frames from a pcap passing them to the callback function provided
-    pub fn read_packets(&mut self, callback_fn: Option<CbFn>) -> crate::Result<()> {
-        if self.mode != PcapNgOpenMode::Read {
-            return Err(OperationOnlySupportedInReadMode);
+    /// Writes `value` in a copyable custom block encoded by `codec`
+    pub fn write_custom_value<T, C: CustomBlockCodec<T>>(&mut self, codec: &C, value: &T) -> crate::Result<()> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
         }
-        unsafe {
-            if let Some(fh) = self.file_handle {
-                let callback_function = callback_fn.map(|callback_function| { callback_function as *mut libc::c_void }).unwrap_or(null_mut());
-                libpcapng_fp_read(fh, Some(callback), callback_function);
-                Ok(())
-            } else {
-                Err(FileNotOpen)
+        let block = Block::Custom(CustomBlock { copyable: true, pen: codec.pen(), data: codec.encode(value) });
+        self.write_bytes(&block.encode()?)
+    }
+
+    /// Reads the rest of the pcap and returns every custom block, decoded by `registry` when it
+    /// has a codec for the block's Private Enterprise Number
+    pub fn read_custom_values<T>(&mut self, registry: &CustomBlockRegistry<T>) -> crate::Result<Vec<CustomValue<T>>> {
+        let mut values = Vec::new();
+        for block in self.blocks()? {
+            if let Block::Custom(custom) = block? {
+                values.push(registry.decode(custom)?);
             }
         }
+        Ok(values)
+    }
+
+    /// Writes a packet frame captured on interface 0 to the pcap, timestamped with the current time
+    pub fn write_packet(&mut self, data: Vec<u8>) -> crate::Result<()> {
+        self.write_packet_on(0, data)
+    }
+
+    /// Writes a packet captured on interface 0 to the pcap including the timestamp
+    ///
+    /// The timestamp can be a `SystemTime`, a `Duration` since the Unix epoch, a `(ticks, TsResolution)`
+    /// pair or whole seconds as a `u32`. It is converted to the interface's `if_tsresol` and split into
+    /// the high and low timestamp words of the enhanced packet block.
+    pub fn write_packet_with_time<T: Into<Timestamp>>(&mut self, data: Vec<u8>, timestamp: T) -> crate::Result<()> {
+        self.write_packet_on_with_time(0, data, timestamp)
+    }
+
+    /// Writes a packet frame captured on the given interface to the pcap, timestamped with the current time
+    ///
+    /// The interface id must have been returned by [`PcapNg::add_interface`] or belong to an
+    /// interface already in the file, otherwise [`PcapNgError::UnknownInterface`](crate::PcapNgError::UnknownInterface) is returned.
+    pub fn write_packet_on(&mut self, interface_id: u32, data: Vec<u8>) -> crate::Result<()> {
+        self.write_packet_on_with_time(interface_id, data, SystemTime::now())
+    }
+
+    /// Writes a packet captured on the given interface to the pcap including the timestamp, see
+    /// [`PcapNg::write_packet_with_time`] and [`PcapNg::write_packet_on`]
+    pub fn write_packet_on_with_time<T: Into<Timestamp>>(&mut self, interface_id: u32, data: Vec<u8>, timestamp: T) -> crate::Result<()> {
+        let original_len = data.len() as u32;
+        self.write_enhanced_packet(interface_id, data, original_len, timestamp.into(), &PacketOptions::default())
+    }
+
+    /// Writes a packet captured on the given interface to the pcap with a timestamp and options
+    /// such as a comment or the packet's direction
+    pub fn write_packet_with_options<T: Into<Timestamp>>(&mut self, interface_id: u32, data: Vec<u8>, timestamp: T, options: &PacketOptions) -> crate::Result<()> {
+        let original_len = data.len() as u32;
+        self.write_enhanced_packet(interface_id, data, original_len, timestamp.into(), options)
+    }
+
+    /// Writes the first bytes of a packet to the pcap while recording the length of the whole
+    /// packet, for example when payloads are cut short for privacy
+    ///
+    /// `original_len` must be at least the length of `data`.
+    pub fn write_truncated_packet<T: Into<Timestamp>>(&mut self, interface_id: u32, data: Vec<u8>, original_len: u32, timestamp: T, options: &PacketOptions) -> crate::Result<()> {
+        if (original_len as usize) < data.len() {
+            return Err(OriginalLengthTooShort(original_len, data.len()));
+        }
+        self.write_enhanced_packet(interface_id, data, original_len, timestamp.into(), options)
     }
 
-    /// Writes a packet to the pcap including the timestamp
-    pub fn write_packet_with_time(&mut self, data: Vec<u8>, timestamp: u32) -> crate::Result<()> {
+    /// When enabled, packets longer than the snaplen of the interface they are written on are cut
+    /// down to the snaplen, their original length is still recorded. Disabled by default.
+    pub fn set_truncate_to_snaplen(&mut self, truncate: bool) {
+        self.truncate_to_snaplen = truncate;
+    }
+
+    fn write_enhanced_packet(&mut self, interface_id: u32, mut data: Vec<u8>, original_len: u32, timestamp: Timestamp, options: &PacketOptions) -> crate::Result<()> {
         if self.mode == PcapNgOpenMode::Read {
             return Err(OperationOnlySupportedInWriteMode);
         }
-        let data_len = data.len() as size_t;
-        let data_bytes = data.as_ptr() as *mut c_uchar;
-        unsafe {
-            if let Some(fh) = self.file_handle {
-                libpcapng_write_enhanced_packet_with_time_to_file(fh, data_bytes, data_len, timestamp);
-                Ok(())
-            } else {
-                Err(FileNotOpen)
+        let interface = match self.interfaces.get(interface_id as usize) {
+            Some(interface) => interface,
+            None => return Err(UnknownInterface(interface_id)),
+        };
+        if self.truncate_to_snaplen && interface.snaplen != 0 {
+            data.truncate(interface.snaplen as usize);
+        }
+        let timestamp = interface.ticks(timestamp);
+        let block = Block::EnhancedPacket(EnhancedPacket {
+            interface_id,
+            timestamp_high: (timestamp >> 32) as u32,
+            timestamp_low: timestamp as u32,
+            captured_len: data.len() as u32,
+            original_len,
+            data,
+            options: options.to_options(),
+            ..Default::default()
+        });
+        self.write_bytes(&block.encode()?)
+    }
+
+    /// Writes a simple packet block, the smallest packet encoding with no timestamp or options,
+    /// which always belongs to interface 0
+    ///
+    /// The packet is truncated to the interface's snaplen as readers derive the captured length from it.
+    pub fn write_simple_packet(&mut self, mut data: Vec<u8>) -> crate::Result<()> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
+        }
+        let interface = match self.interfaces.first() {
+            Some(interface) => interface,
+            None => return Err(UnknownInterface(0)),
+        };
+        let original_len = data.len() as u32;
+        if interface.snaplen != 0 {
+            data.truncate(interface.snaplen as usize);
+        }
+        self.write_bytes(&Block::SimplePacket(SimplePacket { original_len, data }).encode()?)
+    }
+
+    /// Writes an interface statistics block for the given interface, timestamped with the current time
+    pub fn write_interface_statistics(&mut self, interface_id: u32, statistics: &Statistics) -> crate::Result<()> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
+        }
+        let interface = match self.interfaces.get(interface_id as usize) {
+            Some(interface) => interface,
+            None => return Err(UnknownInterface(interface_id)),
+        };
+        let timestamp = interface.ticks(SystemTime::now().into());
+        let block = Block::InterfaceStatistics(InterfaceStatistics {
+            interface_id,
+            timestamp_high: (timestamp >> 32) as u32,
+            timestamp_low: timestamp as u32,
+            options: statistics.to_options(interface),
+            ..Default::default()
+        });
+        self.write_bytes(&block.encode()?)
+    }
+
+    /// Writes the final statistics of every interface of the section, as returned by `statistics`
+    /// for each interface id, then closes the file
+    pub fn close_with_statistics<F: FnMut(u32) -> Statistics>(&mut self, mut statistics: F) -> crate::Result<()> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
+        }
+        for interface_id in 0..self.interfaces.len() as u32 {
+            self.write_interface_statistics(interface_id, &statistics(interface_id))?;
+        }
+        self.close();
+        Ok(())
+    }
+
+    /// Writes a name resolution block mapping addresses to names, either a [`NameResolution`]
+    /// with DNS server options or a `HashMap<IpAddr, Vec<String>>`
+    pub fn write_name_resolution<N: Into<NameResolution>>(&mut self, name_resolution: N) -> crate::Result<()> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
+        }
+        self.write_bytes(&Block::NameResolution(name_resolution.into()).encode()?)
+    }
+
+    /// Reads the rest of the pcap and returns the names of every address in its name resolution blocks
+    pub fn read_name_resolution(&mut self) -> crate::Result<HashMap<IpAddr, Vec<String>>> {
+        let mut names = HashMap::new();
+        for block in self.blocks()? {
+            if let Block::NameResolution(name_resolution) = block? {
+                name_resolution.merge_into(&mut names);
             }
         }
+        Ok(names)
     }
 
-    /// Close the open file handle
-    pub fn close(&mut self) {
-        unsafe {
-            if let Some(fh) = self.file_handle {
-                fflush(fh);
-                fclose(fh);
+    /// Writes a decryption secrets block, such as the contents of an `SSLKEYLOGFILE`
+    ///
+    /// Secrets should be written before the first packet they decrypt.
+    pub fn write_decryption_secrets(&mut self, kind: SecretsKind, secrets: Vec<u8>) -> crate::Result<()> {
+        if self.mode == PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInWriteMode);
+        }
+        self.write_bytes(&Block::DecryptionSecrets(DecryptionSecrets::new(kind, secrets)).encode()?)
+    }
+
+    /// Reads the rest of the pcap and returns the secrets of every decryption secrets block in order
+    pub fn read_decryption_secrets(&mut self) -> crate::Result<Vec<(SecretsKind, Vec<u8>)>> {
+        let mut secrets = Vec::new();
+        for block in self.blocks()? {
+            if let Block::DecryptionSecrets(dsb) = block? {
+                secrets.push((dsb.kind(), dsb.data));
             }
         }
+        Ok(secrets)
+    }
+
+    /// Reads all the frames from a pcap passing them to the callback provided
+    ///
+    /// The callback receives the block's header fields and its body followed by the trailing
+    /// block length. Returning `ControlFlow::Break` stops reading and the error it carries is
+    /// returned as [`PcapNgError::CallbackError`](crate::PcapNgError::CallbackError).
+    pub fn read_packets<F, E>(&mut self, callback_fn: F) -> crate::Result<()>
+    where
+        F: FnMut(BlockInfo, &[u8]) -> ControlFlow<E>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        if self.mode != PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInReadMode);
+        }
+        match self.file.as_mut() {
+            Some(file) => file.read_packets(callback_fn),
+            None => Err(FileNotOpen),
+        }
+    }
+
+    /// Returns an iterator which reads and decodes the blocks of the pcap one at a time
+    ///
+    /// Unlike [`PcapNg::read_packets`] nothing is read ahead of the iterator, so dropping it
+    /// stops reading early and the remaining blocks are left in the file.
+    pub fn blocks(&mut self) -> crate::Result<Blocks<'_>> {
+        self.check_readable()?;
+        Ok(Blocks::new(self))
+    }
+
+    /// Returns an iterator which reads the blocks of the pcap one at a time without decoding them
+    pub fn raw_blocks(&mut self) -> crate::Result<RawBlocks<'_>> {
+        self.check_readable()?;
+        Ok(RawBlocks::new(self))
     }
-}
 
+    fn check_readable(&self) -> crate::Result<()> {
+        if self.mode != PcapNgOpenMode::Read {
+            return Err(OperationOnlySupportedInReadMode);
+        }
+        if self.file.is_none() {
+            return Err(FileNotOpen);
+        }
+        Ok(())
+    }
+
+    /// Writes all of `bytes` to the open file
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> crate::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.write(bytes),
+            None => Err(FileNotOpen),
+        }
+    }
+
+    /// Reads up to `buf.len()` bytes from the open file returning how many were read, a short
+    /// count means the end of the file was reached
+    pub(crate) fn read_bytes(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        match self.file.as_mut() {
+            Some(file) => file.read(buf),
+            None => Err(FileNotOpen),
+        }
+    }
 
-#[no_mangle]
-unsafe extern "C" fn callback(block_counter: u32, block_type: u32, block_total_length: u32, data: *mut c_uchar, userdata: *mut c_void) -> c_int {
-    let bytes: Vec<u8> = std::slice::from_raw_parts(data, block_total_length as usize - 8).iter().map(|x| { x.to_owned() }).collect();
-    if let Some(fn_ptr) = userdata.as_mut() {
-        let cb = transmute::<VoidPtr, CbFn>(fn_ptr);
-        cb(block_counter, block_type, block_total_length, bytes.to_owned());
+    /// Close the open file handle
+    pub fn close(&mut self) {
+        if let Some(file) = self.file.take() {
+            file.close();
+        }
     }
-    0
 }
 
 /// The mode for opening the pcap file
@@ -0,0 +1,213 @@
+use crate::{PCAPNG_BYTE_ORDER_MAGIC, PCAPNG_SECTION_HEADER_BLOCK};
+use crate::{Block, PcapNg};
+use crate::PcapNgError::{MalformedBlock, UnsupportedByteOrder};
+
+/// The most bytes of a block body read at once
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The header fields of a block handed to a [`PcapNg::read_packets`] callback
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BlockInfo {
+    /// Position of the block in the file, the first block is 1
+    pub block_counter: u32,
+    /// The pcapng block type, one of the `PCAPNG_*_BLOCK` constants for known blocks
+    pub block_type: u32,
+    /// Length of the whole block including the type, length and trailing length fields
+    pub block_total_length: u32,
+}
+
+/// A block read from the pcap before any of its body has been decoded
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RawBlock {
+    /// Position of the block in the file, the first block is 1
+    pub block_counter: u32,
+    /// The pcapng block type, one of the `PCAPNG_*_BLOCK` constants for known blocks
+    pub block_type: u32,
+    /// Length of the whole block including the type, length and trailing length fields
+    pub block_total_length: u32,
+    /// The block body followed by the trailing block total length, the same bytes libpcapng passes to its callbacks
+    pub data: Vec<u8>,
+}
+
+impl RawBlock {
+    /// The header fields of the block
+    pub fn info(&self) -> BlockInfo {
+        BlockInfo {
+            block_counter: self.block_counter,
+            block_type: self.block_type,
+            block_total_length: self.block_total_length,
+        }
+    }
+
+    /// The block body without the trailing block total length, empty when `data` is too short to hold it
+    pub fn body(&self) -> &[u8] {
+        &self.data[..self.data.len().saturating_sub(4)]
+    }
+}
+
+/// Iterator over the undecoded blocks of a pcap, created with [`PcapNg::raw_blocks`]
+///
+/// Each call to `next` reads exactly one block from the file. Once an error has been returned
+/// the iterator is exhausted.
+pub struct RawBlocks<'a> {
+    pcap: &'a mut PcapNg,
+    finished: bool,
+}
+
+impl<'a> RawBlocks<'a> {
+    pub(crate) fn new(pcap: &'a mut PcapNg) -> Self {
+        RawBlocks {
+            pcap,
+            finished: false,
+        }
+    }
+
+    fn read_block(&mut self) -> crate::Result<Option<RawBlock>> {
+        let block_counter = self.pcap.block_counter + 1;
+        let block = read_raw_block(|buf| self.pcap.read_bytes(buf), block_counter)?;
+        if block.is_some() {
+            self.pcap.block_counter = block_counter;
+        }
+        Ok(block)
+    }
+}
+
+/// Reads the next block with `read`, numbering it `block_counter`, returning `None` at the end of
+/// the file
+pub(crate) fn read_raw_block<R>(mut read: R, block_counter: u32) -> crate::Result<Option<RawBlock>>
+where
+    R: FnMut(&mut [u8]) -> crate::Result<usize>,
+{
+    let mut header = [0u8; 8];
+    match read(&mut header)? {
+        0 => return Ok(None),
+        8 => {}
+        _ => return Err(MalformedBlock("truncated block header".to_string())),
+    }
+    let block_type = u32::from_ne_bytes(header[0..4].try_into().unwrap());
+    let block_total_length = u32::from_ne_bytes(header[4..8].try_into().unwrap());
+
+    let mut data = Vec::new();
+    if block_type == PCAPNG_SECTION_HEADER_BLOCK {
+        // the length can only be trusted once the byte order magic has been checked
+        let mut magic = [0u8; 4];
+        if read(&mut magic)? != 4 {
+            return Err(MalformedBlock("truncated section header block".to_string()));
+        }
+        match u32::from_ne_bytes(magic) {
+            PCAPNG_BYTE_ORDER_MAGIC => {}
+            m if m.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => return Err(UnsupportedByteOrder),
+            m => return Err(MalformedBlock(format!("invalid byte order magic {:#010x}", m))),
+        }
+        data.extend_from_slice(&magic);
+    }
+    if block_total_length < 12 || block_total_length % 4 != 0 || (block_total_length as usize) < data.len() + 12 {
+        return Err(MalformedBlock(format!("invalid block total length {}", block_total_length)));
+    }
+
+    // the body is read in chunks so a corrupt length fails on the short read instead of
+    // allocating whatever size the header claims
+    let block_len = block_total_length as usize - 8;
+    while data.len() < block_len {
+        let start = data.len();
+        let chunk = (block_len - start).min(READ_CHUNK_SIZE);
+        data.resize(start + chunk, 0);
+        if read(&mut data[start..])? != chunk {
+            return Err(MalformedBlock("truncated block".to_string()));
+        }
+    }
+    let trailing_length = u32::from_ne_bytes(data[data.len() - 4..].try_into().unwrap());
+    if trailing_length != block_total_length {
+        return Err(MalformedBlock(format!("trailing block length {} does not match {}", trailing_length, block_total_length)));
+    }
+
+    Ok(Some(RawBlock {
+        block_counter,
+        block_type,
+        block_total_length,
+        data,
+    }))
+}
+
+impl Iterator for RawBlocks<'_> {
+    type Item = crate::Result<RawBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.read_block() {
+            Ok(Some(block)) => Some(Ok(block)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterator over the decoded blocks of a pcap, created with [`PcapNg::blocks`]
+///
+/// Each call to `next` reads exactly one block from the file. Once an error has been returned
+/// the iterator is exhausted.
+pub struct Blocks<'a> {
+    raw: RawBlocks<'a>,
+}
+
+impl<'a> Blocks<'a> {
+    pub(crate) fn new(pcap: &'a mut PcapNg) -> Self {
+        Blocks {
+            raw: RawBlocks::new(pcap),
+        }
+    }
+}
+
+impl Blocks<'_> {
+    /// Keeps track of the interfaces of the current section and fills in the timestamp
+    /// resolution and offset of packets from the interface they were captured on
+    fn track_section(&mut self, block: &mut Block) {
+        let interfaces = &mut self.raw.pcap.interfaces;
+        match block {
+            Block::SectionHeader(_) => interfaces.clear(),
+            Block::InterfaceDescription(interface) => interfaces.push(interface.clone()),
+            Block::EnhancedPacket(packet) => {
+                if let Some(interface) = interfaces.get(packet.interface_id as usize) {
+                    packet.ts_resolution = interface.ts_resolution();
+                    packet.ts_offset = interface.ts_offset();
+                }
+            }
+            Block::SimplePacket(packet) => {
+                // simple packets belong to the first interface and are never longer than its snaplen
+                if let Some(interface) = interfaces.first() {
+                    if interface.snaplen != 0 {
+                        packet.data.truncate(interface.snaplen as usize);
+                    }
+                }
+            }
+            Block::InterfaceStatistics(statistics) => {
+                if let Some(interface) = interfaces.get(statistics.interface_id as usize) {
+                    statistics.ts_resolution = interface.ts_resolution();
+                    statistics.ts_offset = interface.ts_offset();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = crate::Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = self.raw.next()?.and_then(Block::decode);
+        match &mut block {
+            Ok(block) => self.track_section(block),
+            Err(_) => self.raw.finished = true,
+        }
+        Some(block)
+    }
+}
@@ -0,0 +1,96 @@
+use std::fs::OpenOptions;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use crate::reader::read_raw_block;
+use crate::{Block, BlockInfo, CustomBlock, Interface, PcapNgOpenMode, SectionHeader, LINKTYPE_RAW, PCAPNG_PEN, PCAPNG_VERSION_MAJOR, PCAPNG_VERSION_MINOR};
+use crate::PcapNgError::{CallbackError, FileOpenError, FileReadError, FileWriteError};
+
+/// A pcap file read and written with the standard library, producing the same bytes as libpcapng
+pub(crate) enum File {
+    Reader(BufReader<std::fs::File>),
+    Writer(BufWriter<std::fs::File>),
+}
+
+impl File {
+    pub(crate) fn open(path: &Path, mode: &PcapNgOpenMode) -> crate::Result<File> {
+        let file = match mode {
+            PcapNgOpenMode::Write => OpenOptions::new().write(true).create(true).truncate(true).open(path),
+            PcapNgOpenMode::Append => OpenOptions::new().append(true).create(true).open(path),
+            PcapNgOpenMode::Read => OpenOptions::new().read(true).open(path),
+        };
+        match (file, mode) {
+            (Ok(file), PcapNgOpenMode::Read) => Ok(File::Reader(BufReader::new(file))),
+            (Ok(file), _) => Ok(File::Writer(BufWriter::new(file))),
+            (Err(_), _) => Err(FileOpenError),
+        }
+    }
+
+    /// Writes all of `bytes` to the file
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> crate::Result<()> {
+        match self {
+            File::Writer(writer) => writer.write_all(bytes).map_err(|_| FileWriteError),
+            File::Reader(_) => Err(FileWriteError),
+        }
+    }
+
+    /// Reads up to `buf.len()` bytes returning how many were read, a short count means the end
+    /// of the file was reached
+    pub(crate) fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        let reader = match self {
+            File::Reader(reader) => reader,
+            File::Writer(_) => return Err(FileReadError),
+        };
+        let mut read = 0;
+        while read < buf.len() {
+            match reader.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return Err(FileReadError),
+            }
+        }
+        Ok(read)
+    }
+
+    /// Writes the section header and single `LINKTYPE_RAW` interface libpcapng writes, with an
+    /// unspecified section length of 0 and no snaplen
+    pub(crate) fn write_legacy_header(&mut self) -> crate::Result<()> {
+        let mut bytes = Block::SectionHeader(SectionHeader {
+            major_version: PCAPNG_VERSION_MAJOR as u16,
+            minor_version: PCAPNG_VERSION_MINOR as u16,
+            section_length: 0,
+            options: Vec::new(),
+        }).encode()?;
+        bytes.extend_from_slice(&Block::InterfaceDescription(Interface::new(LINKTYPE_RAW).into()).encode()?);
+        self.write(&bytes)
+    }
+
+    /// Writes a custom block owned by libpcapng's `PCAPNG_PEN`
+    pub(crate) fn write_custom(&mut self, data: &[u8]) -> crate::Result<()> {
+        self.write(&Block::Custom(CustomBlock { copyable: true, pen: PCAPNG_PEN, data: data.to_vec() }).encode()?)
+    }
+
+    /// Reads the rest of the file passing every block to the callback, numbered from 1 as
+    /// libpcapng does
+    pub(crate) fn read_packets<F, E>(&mut self, mut callback_fn: F) -> crate::Result<()>
+    where
+        F: FnMut(BlockInfo, &[u8]) -> ControlFlow<E>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut block_counter = 1;
+        while let Some(raw) = read_raw_block(|buf| self.read(buf), block_counter)? {
+            if let ControlFlow::Break(e) = callback_fn(raw.info(), &raw.data) {
+                return Err(CallbackError(e.into()));
+            }
+            block_counter += 1;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn close(self) {
+        if let File::Writer(mut writer) = self {
+            let _ = writer.flush();
+        }
+    }
+}
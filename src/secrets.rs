@@ -0,0 +1,53 @@
+use crate::{PCAPNG_TLS_KEY_LOG, PCAPNG_WIREGUARD_KEY_LOG, PCAPNG_ZIGBEE_APS_KEY, PCAPNG_ZIGBEE_NWK_KEY};
+use crate::DecryptionSecrets;
+
+/// The format of the secrets in a decryption secrets block
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SecretsKind {
+    /// TLS key log, the contents of an `SSLKEYLOGFILE`
+    TlsKeyLog,
+    /// WireGuard key log, as written by `extract-handshakes.sh`
+    WireGuardKeyLog,
+    /// ZigBee network keys
+    ZigBeeNwkKey,
+    /// ZigBee application support keys
+    ZigBeeApsKey,
+    /// Any other secrets type
+    Other(u32),
+}
+
+impl From<u32> for SecretsKind {
+    fn from(secrets_type: u32) -> Self {
+        match secrets_type {
+            PCAPNG_TLS_KEY_LOG => SecretsKind::TlsKeyLog,
+            PCAPNG_WIREGUARD_KEY_LOG => SecretsKind::WireGuardKeyLog,
+            PCAPNG_ZIGBEE_NWK_KEY => SecretsKind::ZigBeeNwkKey,
+            PCAPNG_ZIGBEE_APS_KEY => SecretsKind::ZigBeeApsKey,
+            other => SecretsKind::Other(other),
+        }
+    }
+}
+
+impl From<SecretsKind> for u32 {
+    fn from(kind: SecretsKind) -> Self {
+        match kind {
+            SecretsKind::TlsKeyLog => PCAPNG_TLS_KEY_LOG,
+            SecretsKind::WireGuardKeyLog => PCAPNG_WIREGUARD_KEY_LOG,
+            SecretsKind::ZigBeeNwkKey => PCAPNG_ZIGBEE_NWK_KEY,
+            SecretsKind::ZigBeeApsKey => PCAPNG_ZIGBEE_APS_KEY,
+            SecretsKind::Other(secrets_type) => secrets_type,
+        }
+    }
+}
+
+impl DecryptionSecrets {
+    /// Creates a block holding `data` in the format given by `kind`
+    pub fn new(kind: SecretsKind, data: Vec<u8>) -> Self {
+        DecryptionSecrets { secrets_type: kind.into(), data, options: Vec::new() }
+    }
+
+    /// The format of the secrets
+    pub fn kind(&self) -> SecretsKind {
+        self.secrets_type.into()
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{BlockOption, SectionHeader};
+
+/// Builder for the Section Header Block written by [`PcapNg::set_section_header`](crate::PcapNg::set_section_header)
+///
+/// ```rust
+/// use libpcapng_rs::Section;
+///
+/// let section = Section::new()
+///     .hardware("x86_64")
+///     .os("Linux 6.1")
+///     .user_application("collector 2.3")
+///     .comment("rack 12");
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Section {
+    hardware: Option<String>,
+    os: Option<String>,
+    user_application: Option<String>,
+    comments: Vec<String>,
+}
+
+impl Section {
+    /// Creates a section header without options
+    pub fn new() -> Self {
+        Section::default()
+    }
+
+    /// Sets `shb_hardware`, the hardware the file is created on
+    pub fn hardware<S: Into<String>>(mut self, hardware: S) -> Self {
+        self.hardware = Some(hardware.into());
+        self
+    }
+
+    /// Sets `shb_os`, the operating system the file is created on
+    pub fn os<S: Into<String>>(mut self, os: S) -> Self {
+        self.os = Some(os.into());
+        self
+    }
+
+    /// Sets `shb_userappl`, the application creating the file
+    pub fn user_application<S: Into<String>>(mut self, user_application: S) -> Self {
+        self.user_application = Some(user_application.into());
+        self
+    }
+
+    /// Adds an `opt_comment`, can be called more than once
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> Self {
+        self.comments.push(comment.into());
+        self
+    }
+}
+
+impl From<Section> for SectionHeader {
+    fn from(section: Section) -> Self {
+        let mut options: Vec<BlockOption> = section.comments.into_iter().map(BlockOption::Comment).collect();
+        if let Some(hardware) = section.hardware {
+            options.push(BlockOption::ShbHardware(hardware));
+        }
+        if let Some(os) = section.os {
+            options.push(BlockOption::ShbOs(os));
+        }
+        if let Some(user_application) = section.user_application {
+            options.push(BlockOption::ShbUserAppl(user_application));
+        }
+        SectionHeader {
+            major_version: 1,
+            minor_version: 0,
+            section_length: -1,
+            options,
+        }
+    }
+}
+
+impl SectionHeader {
+    /// The `shb_hardware` option, the hardware the section was created on
+    pub fn hardware(&self) -> Option<&str> {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::ShbHardware(hardware) => Some(hardware.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The `shb_os` option, the operating system the section was created on
+    pub fn os(&self) -> Option<&str> {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::ShbOs(os) => Some(os.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The `shb_userappl` option, the application which created the section
+    pub fn user_application(&self) -> Option<&str> {
+        self.options.iter().find_map(|option| match option {
+            BlockOption::ShbUserAppl(user_application) => Some(user_application.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The `opt_comment` options of the section
+    pub fn comments(&self) -> Vec<&str> {
+        self.options.iter().filter_map(|option| match option {
+            BlockOption::Comment(comment) => Some(comment.as_str()),
+            _ => None,
+        }).collect()
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::SystemTime;
+use crate::timestamp::ticks_to_system_time;
+use crate::{BlockOption, InterfaceDescription, InterfaceStatistics};
+
+/// Capture statistics of an interface, written with [`PcapNg::write_interface_statistics`](crate::PcapNg::write_interface_statistics)
+/// and read with [`InterfaceStatistics::statistics`]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Statistics {
+    /// `isb_starttime`, when the statistics started being collected
+    pub start_time: Option<SystemTime>,
+    /// `isb_endtime`, when the statistics were last updated
+    pub end_time: Option<SystemTime>,
+    /// `isb_ifrecv`, packets received by the interface
+    pub if_recv: Option<u64>,
+    /// `isb_ifdrop`, packets dropped by the interface because of a lack of resources
+    pub if_drop: Option<u64>,
+    /// `isb_filteraccept`, packets accepted by the capture filter
+    pub filter_accept: Option<u64>,
+    /// `isb_osdrop`, packets dropped by the operating system
+    pub os_drop: Option<u64>,
+    /// `isb_usrdeliv`, packets delivered to the user
+    pub usr_deliv: Option<u64>,
+}
+
+impl Statistics {
+    /// Converts the statistics into block options, with times in the resolution of `interface`
+    pub(crate) fn to_options(&self, interface: &InterfaceDescription) -> Vec<BlockOption> {
+        let mut options = Vec::new();
+        if let Some(start_time) = self.start_time {
+            options.push(BlockOption::IsbStartTime(interface.ticks(start_time.into())));
+        }
+        if let Some(end_time) = self.end_time {
+            options.push(BlockOption::IsbEndTime(interface.ticks(end_time.into())));
+        }
+        let counters = [
+            (self.if_recv, BlockOption::IsbIfRecv as fn(u64) -> BlockOption),
+            (self.if_drop, BlockOption::IsbIfDrop),
+            (self.filter_accept, BlockOption::IsbFilterAccept),
+            (self.os_drop, BlockOption::IsbOsDrop),
+            (self.usr_deliv, BlockOption::IsbUsrDeliv),
+        ];
+        for (counter, option) in counters {
+            if let Some(counter) = counter {
+                options.push(option(counter));
+            }
+        }
+        options
+    }
+}
+
+impl InterfaceStatistics {
+    /// The time the statistics were taken
+    pub fn timestamp(&self) -> SystemTime {
+        let ticks = (self.timestamp_high as u64) << 32 | self.timestamp_low as u64;
+        ticks_to_system_time(ticks, self.ts_resolution, self.ts_offset)
+    }
+
+    /// The statistics carried by the block's options
+    pub fn statistics(&self) -> Statistics {
+        let mut statistics = Statistics::default();
+        for option in &self.options {
+            match option {
+                BlockOption::IsbStartTime(ticks) => statistics.start_time = Some(ticks_to_system_time(*ticks, self.ts_resolution, self.ts_offset)),
+                BlockOption::IsbEndTime(ticks) => statistics.end_time = Some(ticks_to_system_time(*ticks, self.ts_resolution, self.ts_offset)),
+                BlockOption::IsbIfRecv(count) => statistics.if_recv = Some(*count),
+                BlockOption::IsbIfDrop(count) => statistics.if_drop = Some(*count),
+                BlockOption::IsbFilterAccept(count) => statistics.filter_accept = Some(*count),
+                BlockOption::IsbOsDrop(count) => statistics.os_drop = Some(*count),
+                BlockOption::IsbUsrDeliv(count) => statistics.usr_deliv = Some(*count),
+                _ => {}
+            }
+        }
+        statistics
+    }
+}
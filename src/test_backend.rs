@@ -0,0 +1,79 @@
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::path::Path;
+use crate::{libpcapng_backend, rust_backend, BlockInfo, PcapNgOpenMode};
+
+thread_local! {
+    static RUST_BACKEND: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` with the files it opens going through the native backend instead of libpcapng, so
+/// tests can drive the same [`PcapNg`](crate::PcapNg) calls through both backends
+pub(crate) fn with_rust_backend<T>(f: impl FnOnce() -> T) -> T {
+    RUST_BACKEND.with(|rust| rust.set(true));
+    let result = f();
+    RUST_BACKEND.with(|rust| rust.set(false));
+    result
+}
+
+/// A pcap file going through the backend selected when it was opened
+pub(crate) enum File {
+    Libpcapng(libpcapng_backend::File),
+    Rust(rust_backend::File),
+}
+
+impl File {
+    pub(crate) fn open(path: &Path, mode: &PcapNgOpenMode) -> crate::Result<File> {
+        if RUST_BACKEND.with(Cell::get) {
+            rust_backend::File::open(path, mode).map(File::Rust)
+        } else {
+            libpcapng_backend::File::open(path, mode).map(File::Libpcapng)
+        }
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> crate::Result<()> {
+        match self {
+            File::Libpcapng(file) => file.write(bytes),
+            File::Rust(file) => file.write(bytes),
+        }
+    }
+
+    pub(crate) fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        match self {
+            File::Libpcapng(file) => file.read(buf),
+            File::Rust(file) => file.read(buf),
+        }
+    }
+
+    pub(crate) fn write_legacy_header(&mut self) -> crate::Result<()> {
+        match self {
+            File::Libpcapng(file) => file.write_legacy_header(),
+            File::Rust(file) => file.write_legacy_header(),
+        }
+    }
+
+    pub(crate) fn write_custom(&mut self, data: &[u8]) -> crate::Result<()> {
+        match self {
+            File::Libpcapng(file) => file.write_custom(data),
+            File::Rust(file) => file.write_custom(data),
+        }
+    }
+
+    pub(crate) fn read_packets<F, E>(&mut self, callback_fn: F) -> crate::Result<()>
+    where
+        F: FnMut(BlockInfo, &[u8]) -> ControlFlow<E>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        match self {
+            File::Libpcapng(file) => file.read_packets(callback_fn),
+            File::Rust(file) => file.read_packets(callback_fn),
+        }
+    }
+
+    pub(crate) fn close(self) {
+        match self {
+            File::Libpcapng(file) => file.close(),
+            File::Rust(file) => file.close(),
+        }
+    }
+}
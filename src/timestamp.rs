@@ -0,0 +1,131 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The resolution of timestamps on an interface, the `if_tsresol` option
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TsResolution {
+    /// Each tick is 10^-n seconds
+    Decimal(u8),
+    /// Each tick is 2^-n seconds
+    Binary(u8),
+}
+
+impl TsResolution {
+    /// One tick per microsecond, the default when an interface has no `if_tsresol` option
+    pub const MICROSECONDS: TsResolution = TsResolution::Decimal(6);
+    /// One tick per nanosecond
+    pub const NANOSECONDS: TsResolution = TsResolution::Decimal(9);
+
+    /// Decodes the single byte value of an `if_tsresol` option
+    pub fn from_byte(byte: u8) -> Self {
+        if byte & 0x80 == 0 {
+            TsResolution::Decimal(byte)
+        } else {
+            TsResolution::Binary(byte & 0x7f)
+        }
+    }
+
+    /// Encodes the resolution as the single byte value of an `if_tsresol` option
+    pub fn to_byte(self) -> u8 {
+        match self {
+            TsResolution::Decimal(exponent) => exponent & 0x7f,
+            TsResolution::Binary(exponent) => 0x80 | (exponent & 0x7f),
+        }
+    }
+}
+
+impl Default for TsResolution {
+    fn default() -> Self {
+        TsResolution::MICROSECONDS
+    }
+}
+
+impl TsResolution {
+    /// Number of ticks in one second, `None` when it does not fit in a `u128`
+    pub(crate) fn ticks_per_second(self) -> Option<u128> {
+        match self {
+            TsResolution::Decimal(exponent) => 10u128.checked_pow(exponent as u32),
+            TsResolution::Binary(exponent) => 2u128.checked_pow(exponent as u32),
+        }
+    }
+}
+
+/// A packet timestamp, converted to the resolution of the interface the packet is written on
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Timestamp {
+    /// A point in time, times before the Unix epoch are written as the epoch
+    SystemTime(SystemTime),
+    /// Time elapsed since the Unix epoch
+    SinceEpoch(Duration),
+    /// Ticks of the given resolution elapsed since the Unix epoch
+    Ticks(u64, TsResolution),
+}
+
+impl Timestamp {
+    /// Converts the timestamp to ticks of `resolution` since the Unix epoch, saturating at `u64::MAX`
+    pub fn to_ticks(self, resolution: TsResolution) -> u64 {
+        let (ticks, from) = match self {
+            Timestamp::Ticks(ticks, from) if from == resolution => return ticks,
+            Timestamp::Ticks(ticks, from) => (ticks as u128, from),
+            Timestamp::SystemTime(time) => (time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos(), TsResolution::NANOSECONDS),
+            Timestamp::SinceEpoch(duration) => (duration.as_nanos(), TsResolution::NANOSECONDS),
+        };
+        match (from.ticks_per_second(), resolution.ticks_per_second()) {
+            (Some(from), Some(to)) => ticks.checked_mul(to).map(|t| t / from).unwrap_or(u128::MAX).min(u64::MAX as u128) as u64,
+            _ => u64::MAX,
+        }
+    }
+}
+
+impl Timestamp {
+    /// Converts the timestamp to a point in time, saturating when it does not fit in a `SystemTime`
+    pub fn to_system_time(self) -> SystemTime {
+        let since_epoch = match self {
+            Timestamp::SystemTime(time) => return time,
+            Timestamp::SinceEpoch(duration) => duration,
+            Timestamp::Ticks(ticks, resolution) => match resolution.ticks_per_second() {
+                Some(ticks_per_second) => {
+                    let ticks = ticks as u128;
+                    let seconds = (ticks / ticks_per_second).min(u64::MAX as u128) as u64;
+                    let nanos = (ticks % ticks_per_second * 1_000_000_000 / ticks_per_second) as u32;
+                    Duration::new(seconds, nanos)
+                }
+                // a tick is shorter than anything a u64 of them could add up to
+                None => Duration::ZERO,
+            },
+        };
+        UNIX_EPOCH.checked_add(since_epoch).unwrap_or(UNIX_EPOCH + Duration::from_secs(u32::MAX as u64))
+    }
+}
+
+/// Converts ticks of an interface's resolution to a point in time, adding the interface's offset in seconds
+pub(crate) fn ticks_to_system_time(ticks: u64, resolution: TsResolution, offset: i64) -> SystemTime {
+    let time = Timestamp::Ticks(ticks, resolution).to_system_time();
+    let offset_duration = Duration::from_secs(offset.unsigned_abs());
+    let adjusted = if offset < 0 { time.checked_sub(offset_duration) } else { time.checked_add(offset_duration) };
+    adjusted.unwrap_or(time)
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        Timestamp::SystemTime(time)
+    }
+}
+
+impl From<Duration> for Timestamp {
+    fn from(duration: Duration) -> Self {
+        Timestamp::SinceEpoch(duration)
+    }
+}
+
+impl From<(u64, TsResolution)> for Timestamp {
+    fn from((ticks, resolution): (u64, TsResolution)) -> Self {
+        Timestamp::Ticks(ticks, resolution)
+    }
+}
+
+/// Whole seconds since the Unix epoch
+impl From<u32> for Timestamp {
+    fn from(seconds: u32) -> Self {
+        Timestamp::SinceEpoch(Duration::from_secs(seconds as u64))
+    }
+}